@@ -1,5 +1,7 @@
 use num_bigint::BigInt as NumBigInt;
-use substreams::scalar::BigInt;
+use num_traits::Num;
+use std::str::FromStr;
+use substreams::scalar::{BigDecimal, BigInt};
 
 /// Format BigInt as a string, handling potential edge cases
 #[inline]
@@ -7,6 +9,154 @@ pub fn format_bigint(value: &BigInt) -> String {
     value.to_string()
 }
 
+/// Format BigDecimal as a string, handling potential edge cases
+#[inline]
+pub fn format_bigdecimal(value: &BigDecimal) -> String {
+    value.to_string()
+}
+
+/// Selects whether large integer/decimal ticker fields are rendered as
+/// base-10 strings or `0x`-prefixed hex, mirroring the `HexOrDecimalU256`
+/// convention used elsewhere in the Rust DeFi ecosystem. Hex is more compact
+/// for 256-bit values and avoids precision confusion in EVM-style tooling;
+/// decimal remains the default for backward compatibility.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NumericEncoding {
+    Decimal,
+    Hex,
+}
+
+impl NumericEncoding {
+    /// Parse from a map module's `params` string (format: `key=value,key=value,...`).
+    /// Recognizes `encoding=hex` anywhere in the string; anything else,
+    /// including an absent `encoding` key, defaults to decimal.
+    pub fn from_params(params: &str) -> Self {
+        for kv in params.split(',') {
+            let mut parts = kv.splitn(2, '=');
+            if let (Some("encoding"), Some(value)) = (parts.next(), parts.next()) {
+                if value.eq_ignore_ascii_case("hex") {
+                    return NumericEncoding::Hex;
+                }
+            }
+        }
+        NumericEncoding::Decimal
+    }
+}
+
+/// Format a BigInt per `encoding`, as `0x`-prefixed hex or decimal.
+pub fn format_bigint_encoded(value: &BigInt, encoding: NumericEncoding) -> String {
+    match encoding {
+        NumericEncoding::Decimal => format_bigint(value),
+        NumericEncoding::Hex => format!("0x{:x}", NumBigInt::try_from(value.clone()).unwrap_or_default()),
+    }
+}
+
+/// Format a BigDecimal per `encoding`. Ticker volume fields are always
+/// integral raw token units even though they're carried as BigDecimal, so hex
+/// mode renders the integer part; a value with a nonzero fractional part
+/// (e.g. a derived price) falls back to decimal, since hex can't represent it.
+pub fn format_bigdecimal_encoded(value: &BigDecimal, encoding: NumericEncoding) -> String {
+    match encoding {
+        NumericEncoding::Decimal => format_bigdecimal(value),
+        NumericEncoding::Hex => {
+            let decimal_str = format_bigdecimal(value);
+            match NumBigInt::from_str(&decimal_str) {
+                Ok(int_value) => format!("0x{:x}", int_value),
+                Err(_) => decimal_str,
+            }
+        }
+    }
+}
+
+/// Parse a `0x`-prefixed hex or base-10 decimal string into a BigInt, the
+/// counterpart to [`format_bigint_encoded`] for consumers that need to read
+/// either encoding back.
+pub fn parse_bigint(value: &str) -> Option<BigInt> {
+    if let Some(hex_str) = value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        NumBigInt::from_str_radix(hex_str, 16).ok().map(BigInt::from)
+    } else {
+        NumBigInt::from_str(value).ok().map(BigInt::from)
+    }
+}
+
+/// Derive price = (sqrtPriceX96 / 2^96)^2 from a sqrtPriceX96 BigInt.
+///
+/// Shared by V3 (which reads sqrtPriceX96 straight off the swap event) and V2
+/// (which first reconstructs a V3-equivalent sqrtPriceX96 from reserves via
+/// `calculate_sqrt_price_x96`), so both report price in the same units.
+pub fn price_from_sqrt_price_x96(sqrt_price_x96: &BigInt) -> BigDecimal {
+    if sqrt_price_x96 == &BigInt::zero() {
+        return BigDecimal::zero();
+    }
+
+    let sqrt_price = BigDecimal::from(sqrt_price_x96.clone()) / two_pow_96();
+    sqrt_price.clone() * sqrt_price
+}
+
+fn two_pow_96() -> BigDecimal {
+    BigDecimal::from_str("79228162514264337593543950336").unwrap()
+}
+
+/// Derive a decimal-adjusted price from sqrtPriceX96, staying in integer
+/// arithmetic the whole way through instead of dividing by `2^96` as a
+/// `BigDecimal` (which silently loses precision for extreme prices and never
+/// accounts for token decimals, the way [`price_from_sqrt_price_x96`] does).
+///
+/// `ratio = sqrtPriceX96^2` is formed directly as a ~384-bit integer, then
+/// `numerator = ratio * 10^(decimals0 + out_precision)` and
+/// `denominator = 2^192 * 10^decimals1` are divided once; the decimal point
+/// is inserted `out_precision` digits from the right of the integer quotient,
+/// matching Uniswap's on-chain Q64.96 semantics. Returns `"0"` if
+/// `sqrt_price_x96` is zero.
+pub fn price_from_sqrt_x96(
+    sqrt_price_x96: &BigInt,
+    decimals0: u32,
+    decimals1: u32,
+    out_precision: u32,
+) -> String {
+    if sqrt_price_x96 == &BigInt::zero() {
+        return "0".to_string();
+    }
+
+    let Ok(sqrt_price) = NumBigInt::try_from(sqrt_price_x96.clone()) else {
+        return "0".to_string();
+    };
+
+    let ratio = &sqrt_price * &sqrt_price;
+    let ten = NumBigInt::from(10u32);
+    let numerator = ratio * ten.pow(decimals0 + out_precision);
+    let denominator = (NumBigInt::from(1u32) << 192) * ten.pow(decimals1);
+
+    let quotient = numerator / denominator;
+    insert_decimal_point(&quotient.to_string(), out_precision)
+}
+
+/// Insert a decimal point `out_precision` digits from the right of `digits`
+/// (left-padding with zeros if the integer is shorter), then strip trailing
+/// zeros from the fractional part, dropping the decimal point if none remain.
+fn insert_decimal_point(digits: &str, out_precision: u32) -> String {
+    let out_precision = out_precision as usize;
+    if out_precision == 0 {
+        return digits.to_string();
+    }
+
+    let padded = if digits.len() <= out_precision {
+        format!("{digits:0>width$}", width = out_precision + 1)
+    } else {
+        digits.to_string()
+    };
+
+    let split_at = padded.len() - out_precision;
+    let (whole, frac) = padded.split_at(split_at);
+    let frac_trimmed = frac.trim_end_matches('0');
+
+    if frac_trimmed.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{whole}.{frac_trimmed}")
+    }
+}
+
 /// Ensure address has 0x prefix
 #[inline]
 pub fn ensure_0x_prefix(address: &str) -> String {
@@ -103,6 +253,59 @@ pub fn uint112_to_bigint(bytes: &[u8]) -> BigInt {
     BigInt::from(bigint)
 }
 
+/// Convert unsigned uint128 bytes (stored in 32 bytes) to BigInt
+///
+/// ## Why uint128:
+/// - Uniswap V3 uses uint128 for the in-range active `liquidity` value
+/// - When stored in a 32-byte word, uint128 is right-aligned (last 16 bytes)
+///
+/// ## Parameters:
+/// - `bytes`: Must be exactly 32 bytes with uint128 in the last 16 bytes
+///
+/// ## Returns:
+/// - The BigInt representation of the uint128 value, or 0 if invalid input
+#[inline]
+pub fn uint128_to_bigint(bytes: &[u8]) -> BigInt {
+    if bytes.len() != 32 {
+        return BigInt::zero();
+    }
+
+    // uint128 is stored in the last 16 bytes of the 32-byte word
+    let start = bytes.len().saturating_sub(16);
+    let bigint = NumBigInt::from_bytes_be(num_bigint::Sign::Plus, &bytes[start..]);
+    BigInt::from(bigint)
+}
+
+/// Convert signed int24 bytes (stored in 32 bytes) to a sign-extended i32
+///
+/// ## Why int24:
+/// - Uniswap V3's `tick` field is a signed 24-bit integer
+/// - When stored in a 32-byte word, int24 is right-aligned (last 3 bytes)
+/// - The low 3 bytes must be sign-extended to recover the correct negative
+///   ticks, since a plain unsigned read would treat them as always positive
+///
+/// ## Parameters:
+/// - `bytes`: Must be exactly 32 bytes with int24 in the last 3 bytes
+///
+/// ## Returns:
+/// - The sign-extended i32 representation of the int24 value, or 0 if invalid input
+#[inline]
+pub fn int24_to_i32(bytes: &[u8]) -> i32 {
+    if bytes.len() != 32 {
+        return 0;
+    }
+
+    let start = bytes.len() - 3;
+    let raw = ((bytes[start] as i32) << 16) | ((bytes[start + 1] as i32) << 8) | (bytes[start + 2] as i32);
+
+    // Sign bit is bit 23 (the top bit of the 24-bit value)
+    if bytes[start] & 0x80 != 0 {
+        raw - (1 << 24)
+    } else {
+        raw
+    }
+}
+
 /// Calculate sqrtPriceX96 from reserve amounts
 ///
 /// ## Uniswap V2 to V3 Price Conversion:
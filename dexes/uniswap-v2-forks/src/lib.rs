@@ -22,10 +22,11 @@ mod pb;
 
 use crate::pb::dex::common::v1::{PoolTicker, TickerOutput};
 use dex_common::{
-    calculate_sqrt_price_x96, ensure_0x_prefix, format_bigint, uint112_to_bigint, uint256_to_bigint,
+    calculate_sqrt_price_x96, ensure_0x_prefix, format_bigdecimal, format_bigint_encoded,
+    price_from_sqrt_price_x96, uint112_to_bigint, uint256_to_bigint, NumericEncoding,
 };
 use std::collections::HashMap;
-use substreams::scalar::BigInt;
+use substreams::scalar::{BigDecimal, BigInt};
 use substreams::Hex;
 use substreams_ethereum::block_view::LogView;
 use substreams_ethereum::pb::eth::v2 as eth;
@@ -46,6 +47,34 @@ struct SwapAggregation {
     volume_token1: BigInt,
     swap_count: u32,
     last_sqrt_price: BigInt,
+    /// Latest reserves seen from a `Sync` event this block, for `reserve0`/
+    /// `reserve1`/`tvl_token1`. Zero if no `Sync` has been observed yet.
+    last_reserve0: BigInt,
+    last_reserve1: BigInt,
+    /// Running high price seen this block (token1/token0)
+    high_price: Option<BigDecimal>,
+    /// Running low price seen this block (token1/token0)
+    low_price: Option<BigDecimal>,
+    /// Sum of price * volume, for the volume-weighted average price
+    vwap_price_volume: BigDecimal,
+    /// Sum of volume, the VWAP denominator
+    vwap_volume: BigDecimal,
+    /// Volume from swaps where token0 flowed out of the pool (a "buy" of
+    /// token0, paid for with token1 flowing in).
+    buy_volume_token0: BigInt,
+    /// Volume from swaps where token0 flowed into the pool (a "sell" of
+    /// token0, bought with token1 flowing out).
+    sell_volume_token0: BigInt,
+    /// Volume from swaps where token1 flowed out of the pool (a "buy" of
+    /// token1).
+    buy_volume_token1: BigInt,
+    /// Volume from swaps where token1 flowed into the pool (a "sell" of
+    /// token1).
+    sell_volume_token1: BigInt,
+    /// Count of swaps where token0 was bought (see `buy_volume_token0`)
+    buy_count: u32,
+    /// Count of swaps where token0 was sold (see `sell_volume_token0`)
+    sell_count: u32,
 }
 
 impl Default for SwapAggregation {
@@ -55,6 +84,69 @@ impl Default for SwapAggregation {
             volume_token1: BigInt::zero(),
             swap_count: 0,
             last_sqrt_price: BigInt::zero(),
+            last_reserve0: BigInt::zero(),
+            last_reserve1: BigInt::zero(),
+            high_price: None,
+            low_price: None,
+            vwap_price_volume: BigDecimal::zero(),
+            vwap_volume: BigDecimal::zero(),
+            buy_volume_token0: BigInt::zero(),
+            sell_volume_token0: BigInt::zero(),
+            buy_volume_token1: BigInt::zero(),
+            sell_volume_token1: BigInt::zero(),
+            buy_count: 0,
+            sell_count: 0,
+        }
+    }
+}
+
+impl SwapAggregation {
+    /// Fold a new price observation into the running high/low.
+    fn observe_price(&mut self, price: &BigDecimal) {
+        self.high_price = Some(match &self.high_price {
+            Some(high) if high > price => high.clone(),
+            _ => price.clone(),
+        });
+        self.low_price = Some(match &self.low_price {
+            Some(low) if low < price => low.clone(),
+            _ => price.clone(),
+        });
+    }
+
+    /// Weight a traded volume by the price it traded at, for VWAP.
+    fn observe_vwap_sample(&mut self, price: &BigDecimal, volume: &BigDecimal) {
+        self.vwap_price_volume = self.vwap_price_volume.clone() + price.clone() * volume.clone();
+        self.vwap_volume = self.vwap_volume.clone() + volume.clone();
+    }
+
+    /// The volume-weighted average price, or zero if no volume was observed.
+    fn vwap(&self) -> BigDecimal {
+        if self.vwap_volume == BigDecimal::zero() {
+            return BigDecimal::zero();
+        }
+        self.vwap_price_volume.clone() / self.vwap_volume.clone()
+    }
+
+    /// Classify a single swap's direction and fold it into the buy/sell
+    /// splits, given each token's in/out amounts (one of each pair is zero
+    /// for a normal two-sided AMM swap). "Buy" and "sell" are both stated
+    /// from token0's perspective: token0 flowing out of the pool is a buy,
+    /// and vice versa for a sell.
+    fn observe_swap_direction(
+        &mut self,
+        amount0_in: &BigInt,
+        amount1_in: &BigInt,
+        amount0_out: &BigInt,
+        amount1_out: &BigInt,
+    ) {
+        if *amount0_in > BigInt::zero() {
+            self.sell_volume_token0 = self.sell_volume_token0.clone() + amount0_in.clone();
+            self.buy_volume_token1 = self.buy_volume_token1.clone() + amount1_out.clone();
+            self.sell_count += 1;
+        } else if *amount1_in > BigInt::zero() {
+            self.sell_volume_token1 = self.sell_volume_token1.clone() + amount1_in.clone();
+            self.buy_volume_token0 = self.buy_volume_token0.clone() + amount0_out.clone();
+            self.buy_count += 1;
         }
     }
 }
@@ -92,9 +184,20 @@ fn process_swap_event(log: &LogView, pool_aggregations: &mut HashMap<Vec<u8>, Sw
 
     // Calculate volumes
     // For V2, volume is the sum of in and out amounts (one will be 0 for each direction)
-    entry.volume_token0 = entry.volume_token0.clone() + amount0_in + amount0_out;
-    entry.volume_token1 = entry.volume_token1.clone() + amount1_in + amount1_out;
+    entry.volume_token0 = entry.volume_token0.clone() + amount0_in.clone() + amount0_out.clone();
+    entry.volume_token1 = entry.volume_token1.clone() + amount1_in.clone() + amount1_out.clone();
     entry.swap_count += 1;
+
+    // Direction follows from which of amount0In/amount1In is non-zero.
+    entry.observe_swap_direction(&amount0_in, &amount1_in, &amount0_out, &amount1_out);
+
+    // Weight VWAP using the price from the most recent Sync (Sync is always
+    // emitted before Swap within a transaction, so this reflects the
+    // post-swap reserves for this exact trade). Skip if no Sync seen yet.
+    if entry.last_sqrt_price > BigInt::zero() {
+        let price = price_from_sqrt_price_x96(&entry.last_sqrt_price);
+        entry.observe_vwap_sample(&price, &BigDecimal::from(amount1_in + amount1_out));
+    }
 }
 
 /// Process a sync event and update pool aggregations
@@ -121,10 +224,23 @@ fn process_sync_event(log: &LogView, pool_aggregations: &mut HashMap<Vec<u8>, Sw
     // Calculate sqrtPriceX96 from reserves to match V3 output format
     // sqrtPriceX96 = sqrt(reserve1 / reserve0) * 2^96
     entry.last_sqrt_price = calculate_sqrt_price_x96(&reserve0, &reserve1);
+    entry.last_reserve0 = reserve0;
+    entry.last_reserve1 = reserve1;
+
+    // Track intra-block high/low off the reserve-derived price
+    let price = price_from_sqrt_price_x96(&entry.last_sqrt_price);
+    entry.observe_price(&price);
 }
 
+/// `params` optionally selects the numeric output encoding via `encoding=hex`
+/// (default `encoding=decimal`), applied to `block_volume_token0/1`,
+/// `sqrt_price_x96`, `reserve0/1` and the buy/sell volume fields.
 #[substreams::handlers::map]
-pub fn map_v2_ticker_output(block: eth::Block) -> Result<TickerOutput, substreams::errors::Error> {
+pub fn map_v2_ticker_output(
+    params: String,
+    block: eth::Block,
+) -> Result<TickerOutput, substreams::errors::Error> {
+    let encoding = NumericEncoding::from_params(&params);
     let mut pool_aggregations: HashMap<Vec<u8>, SwapAggregation> = HashMap::new();
 
     // Process all swap and sync events
@@ -160,12 +276,43 @@ pub fn map_v2_ticker_output(block: eth::Block) -> Result<TickerOutput, substream
     for (pool_address_bytes, aggregation) in pool_aggregations {
         let pool_address = ensure_0x_prefix(&Hex(&pool_address_bytes).to_string());
 
+        // TVL in token1 terms: reserve1 + reserve0 * price. Zero if no Sync
+        // has been observed for this pool yet (same as `last_sqrt_price`).
+        let price = price_from_sqrt_price_x96(&aggregation.last_sqrt_price);
+        let tvl_token1 = BigDecimal::from(aggregation.last_reserve1.clone())
+            + BigDecimal::from(aggregation.last_reserve0.clone()) * price;
+
         tickers.push(PoolTicker {
             pool_address,
-            block_volume_token0: format_bigint(&aggregation.volume_token0),
-            block_volume_token1: format_bigint(&aggregation.volume_token1),
+            block_volume_token0: format_bigint_encoded(&aggregation.volume_token0, encoding),
+            block_volume_token1: format_bigint_encoded(&aggregation.volume_token1, encoding),
             swap_count: aggregation.swap_count,
-            sqrt_price_x96: format_bigint(&aggregation.last_sqrt_price),
+            sqrt_price_x96: format_bigint_encoded(&aggregation.last_sqrt_price, encoding),
+            close_price: format_bigdecimal(&price),
+            liquidity: String::new(),
+            current_tick: 0,
+            vwap: format_bigdecimal(&aggregation.vwap()),
+            high_price: format_bigdecimal(&aggregation.high_price.clone().unwrap_or_default()),
+            low_price: format_bigdecimal(&aggregation.low_price.clone().unwrap_or_default()),
+            // V2 swaps carry no protocol-fee accounting; stays at its zero default.
+            protocol_fees_token0: String::new(),
+            protocol_fees_token1: String::new(),
+            reserve0: format_bigint_encoded(&aggregation.last_reserve0, encoding),
+            reserve1: format_bigint_encoded(&aggregation.last_reserve1, encoding),
+            tvl_token1: format_bigdecimal(&tvl_token1),
+            // This crate has no token-price registry (`store_token_prices`
+            // lives in `universal-dex`), so USD volume isn't resolvable here.
+            volume_token0_usd: String::new(),
+            volume_token1_usd: String::new(),
+            // V2 pools are always a single fixed token0/token1 pair.
+            traded_coin0_index: 0,
+            traded_coin1_index: 1,
+            buy_volume_token0: format_bigint_encoded(&aggregation.buy_volume_token0, encoding),
+            sell_volume_token0: format_bigint_encoded(&aggregation.sell_volume_token0, encoding),
+            buy_volume_token1: format_bigint_encoded(&aggregation.buy_volume_token1, encoding),
+            sell_volume_token1: format_bigint_encoded(&aggregation.sell_volume_token1, encoding),
+            buy_count: aggregation.buy_count,
+            sell_count: aggregation.sell_count,
             block_number: block.number,
             timestamp: timestamp_seconds,
         });
@@ -23,11 +23,12 @@ mod pb;
 
 use crate::pb::dex::common::v1::{PoolTicker, TickerOutput};
 use dex_common::{
-    calculate_price_from_sqrt_x96, ensure_0x_prefix, format_bigdecimal, int256_to_bigdecimal,
-    uint160_to_bigdecimal,
+    calculate_price_from_sqrt_x96, ensure_0x_prefix, format_bigdecimal, format_bigdecimal_encoded,
+    format_bigint, int24_to_i32, int256_to_bigdecimal, uint128_to_bigint, uint160_to_bigdecimal,
+    NumericEncoding,
 };
 use std::collections::HashMap;
-use substreams::scalar::BigDecimal;
+use substreams::scalar::{BigDecimal, BigInt};
 use substreams::Hex;
 use substreams_ethereum::block_view::LogView;
 use substreams_ethereum::pb::eth::v2 as eth;
@@ -44,6 +45,41 @@ struct SwapAggregation {
     volume_token1: BigDecimal,
     swap_count: u32,
     last_sqrt_price: BigDecimal,
+    /// Most recent in-range active liquidity, needed to turn sqrtPriceX96
+    /// into a usable price grid / slippage estimate downstream.
+    last_liquidity: BigInt,
+    /// Most recent tick
+    current_tick: i32,
+    /// Running high price seen this block (token1/token0)
+    high_price: Option<BigDecimal>,
+    /// Running low price seen this block (token1/token0)
+    low_price: Option<BigDecimal>,
+    /// Sum of price * volume, for the volume-weighted average price
+    vwap_price_volume: BigDecimal,
+    /// Sum of volume, the VWAP denominator
+    vwap_volume: BigDecimal,
+    /// Cumulative PancakeSwap V3 protocol fees taken in token0 (0 for
+    /// standard Uniswap V3 pools, which don't emit this field)
+    protocol_fees_token0: BigInt,
+    /// Cumulative PancakeSwap V3 protocol fees taken in token1 (0 for
+    /// standard Uniswap V3 pools, which don't emit this field)
+    protocol_fees_token1: BigInt,
+    /// Volume from swaps where token0 flowed out of the pool (a "buy" of
+    /// token0, paid for with token1 flowing in).
+    buy_volume_token0: BigDecimal,
+    /// Volume from swaps where token0 flowed into the pool (a "sell" of
+    /// token0, bought with token1 flowing out).
+    sell_volume_token0: BigDecimal,
+    /// Volume from swaps where token1 flowed out of the pool (a "buy" of
+    /// token1).
+    buy_volume_token1: BigDecimal,
+    /// Volume from swaps where token1 flowed into the pool (a "sell" of
+    /// token1).
+    sell_volume_token1: BigDecimal,
+    /// Count of swaps where token0 was bought (see `buy_volume_token0`)
+    buy_count: u32,
+    /// Count of swaps where token0 was sold (see `sell_volume_token0`)
+    sell_count: u32,
 }
 
 impl Default for SwapAggregation {
@@ -53,6 +89,71 @@ impl Default for SwapAggregation {
             volume_token1: BigDecimal::zero(),
             swap_count: 0,
             last_sqrt_price: BigDecimal::zero(),
+            last_liquidity: BigInt::zero(),
+            current_tick: 0,
+            high_price: None,
+            low_price: None,
+            vwap_price_volume: BigDecimal::zero(),
+            vwap_volume: BigDecimal::zero(),
+            protocol_fees_token0: BigInt::zero(),
+            protocol_fees_token1: BigInt::zero(),
+            buy_volume_token0: BigDecimal::zero(),
+            sell_volume_token0: BigDecimal::zero(),
+            buy_volume_token1: BigDecimal::zero(),
+            sell_volume_token1: BigDecimal::zero(),
+            buy_count: 0,
+            sell_count: 0,
+        }
+    }
+}
+
+impl SwapAggregation {
+    /// Fold a new price observation into the running high/low.
+    fn observe_price(&mut self, price: &BigDecimal) {
+        self.high_price = Some(match &self.high_price {
+            Some(high) if high > price => high.clone(),
+            _ => price.clone(),
+        });
+        self.low_price = Some(match &self.low_price {
+            Some(low) if low < price => low.clone(),
+            _ => price.clone(),
+        });
+    }
+
+    /// Weight a traded volume by the price it traded at, for VWAP.
+    fn observe_vwap_sample(&mut self, price: &BigDecimal, volume: &BigDecimal) {
+        self.vwap_price_volume = self.vwap_price_volume.clone() + price.clone() * volume.clone();
+        self.vwap_volume = self.vwap_volume.clone() + volume.clone();
+    }
+
+    /// The volume-weighted average price, or zero if no volume was observed.
+    fn vwap(&self) -> BigDecimal {
+        if self.vwap_volume == BigDecimal::zero() {
+            return BigDecimal::zero();
+        }
+        self.vwap_price_volume.clone() / self.vwap_volume.clone()
+    }
+
+    /// Classify a single swap's direction and fold it into the buy/sell
+    /// splits, given each token's absolute in/out amounts (one of each pair
+    /// is zero). "Buy" and "sell" are both stated from token0's
+    /// perspective: token0 flowing out of the pool is a buy, and vice versa
+    /// for a sell.
+    fn observe_swap_direction(
+        &mut self,
+        amount0_in: &BigDecimal,
+        amount1_in: &BigDecimal,
+        amount0_out: &BigDecimal,
+        amount1_out: &BigDecimal,
+    ) {
+        if *amount0_in > BigDecimal::zero() {
+            self.sell_volume_token0 = self.sell_volume_token0.clone() + amount0_in.clone();
+            self.buy_volume_token1 = self.buy_volume_token1.clone() + amount1_out.clone();
+            self.sell_count += 1;
+        } else if *amount1_in > BigDecimal::zero() {
+            self.sell_volume_token1 = self.sell_volume_token1.clone() + amount1_in.clone();
+            self.buy_volume_token0 = self.buy_volume_token0.clone() + amount0_out.clone();
+            self.buy_count += 1;
         }
     }
 }
@@ -83,6 +184,9 @@ fn process_swap_event(log: &LogView, pool_aggregations: &mut HashMap<Vec<u8>, Sw
     // Calculate absolute volumes
     // Swap amounts are signed: negative = tokens out, positive = tokens in
     // We need absolute values since volume tracks total traded regardless of direction
+    let amount0_is_in = amount0 > BigDecimal::zero();
+    let amount1_is_in = amount1 > BigDecimal::zero();
+
     let abs_amount0 = if amount0 < BigDecimal::zero() {
         amount0.neg()
     } else {
@@ -95,20 +199,56 @@ fn process_swap_event(log: &LogView, pool_aggregations: &mut HashMap<Vec<u8>, Sw
         amount1
     };
 
-    entry.volume_token0 = entry.volume_token0.clone() + abs_amount0;
-    entry.volume_token1 = entry.volume_token1.clone() + abs_amount1;
+    entry.volume_token0 = entry.volume_token0.clone() + abs_amount0.clone();
+    entry.volume_token1 = entry.volume_token1.clone() + abs_amount1.clone();
     entry.swap_count += 1;
 
+    // Signed amounts already encode direction: positive = into the pool,
+    // negative = out of it.
+    let zero = BigDecimal::zero();
+    if amount0_is_in {
+        entry.observe_swap_direction(&abs_amount0, &zero, &zero, &abs_amount1);
+    } else if amount1_is_in {
+        entry.observe_swap_direction(&zero, &abs_amount1, &abs_amount0, &zero);
+    }
+
     // Parse sqrtPriceX96 (uint160) - bytes 64-96
     // Note: sqrtPriceX96 is the square root of the price ratio, multiplied by 2^96
     let price_bytes = &log.data()[64..96];
     entry.last_sqrt_price = uint160_to_bigdecimal(price_bytes);
+
+    // Track intra-block high/low and volume-weighted average price
+    if entry.last_sqrt_price > BigDecimal::zero() {
+        let price = calculate_price_from_sqrt_x96(&entry.last_sqrt_price);
+        entry.observe_price(&price);
+        entry.observe_vwap_sample(&price, &abs_amount1);
+    }
+
+    // Parse liquidity (uint128) - bytes 96-128
+    entry.last_liquidity = uint128_to_bigint(&log.data()[96..128]);
+
+    // Parse tick (int24, sign-extended) - bytes 128-160
+    entry.current_tick = int24_to_i32(&log.data()[128..160]);
+
+    // PancakeSwap V3 appends protocolFeesToken0/1 (uint128 each) after the
+    // standard 160-byte layout; standard Uniswap V3 events stop at 160 bytes,
+    // so this is a no-op for them.
+    if log.data().len() >= 224 {
+        entry.protocol_fees_token0 =
+            entry.protocol_fees_token0.clone() + uint128_to_bigint(&log.data()[160..192]);
+        entry.protocol_fees_token1 =
+            entry.protocol_fees_token1.clone() + uint128_to_bigint(&log.data()[192..224]);
+    }
 }
 
+/// `params` optionally selects the numeric output encoding via `encoding=hex`
+/// (default `encoding=decimal`), applied to `block_volume_token0/1`.
 #[substreams::handlers::map]
 pub fn map_v3_ticker_output(
+    params: String,
     block: eth::Block,
 ) -> Result<TickerOutput, substreams::errors::Error> {
+    let encoding = NumericEncoding::from_params(&params);
     let mut pool_aggregations: HashMap<Vec<u8>, SwapAggregation> = HashMap::new();
 
     // Process all swap events
@@ -151,10 +291,36 @@ pub fn map_v3_ticker_output(
 
         tickers.push(PoolTicker {
             pool_address,
-            block_volume_token0: format_bigdecimal(&aggregation.volume_token0),
-            block_volume_token1: format_bigdecimal(&aggregation.volume_token1),
+            block_volume_token0: format_bigdecimal_encoded(&aggregation.volume_token0, encoding),
+            block_volume_token1: format_bigdecimal_encoded(&aggregation.volume_token1, encoding),
             swap_count: aggregation.swap_count,
             close_price: format_bigdecimal(&close_price),
+            liquidity: format_bigint(&aggregation.last_liquidity),
+            current_tick: aggregation.current_tick,
+            vwap: format_bigdecimal(&aggregation.vwap()),
+            high_price: format_bigdecimal(&aggregation.high_price.clone().unwrap_or_default()),
+            low_price: format_bigdecimal(&aggregation.low_price.clone().unwrap_or_default()),
+            protocol_fees_token0: format_bigint(&aggregation.protocol_fees_token0),
+            protocol_fees_token1: format_bigint(&aggregation.protocol_fees_token1),
+            // V3 pools have no token-unit reserves (liquidity is concentrated
+            // and range-bound, not a single reserve pair), so these stay at
+            // their zero default; `liquidity` above already reports depth.
+            reserve0: String::new(),
+            reserve1: String::new(),
+            tvl_token1: String::new(),
+            // This crate has no token-price registry (`store_token_prices`
+            // lives in `universal-dex`), so USD volume isn't resolvable here.
+            volume_token0_usd: String::new(),
+            volume_token1_usd: String::new(),
+            // V3 pools are always a single fixed token0/token1 pair.
+            traded_coin0_index: 0,
+            traded_coin1_index: 1,
+            buy_volume_token0: format_bigdecimal(&aggregation.buy_volume_token0),
+            sell_volume_token0: format_bigdecimal(&aggregation.sell_volume_token0),
+            buy_volume_token1: format_bigdecimal(&aggregation.buy_volume_token1),
+            sell_volume_token1: format_bigdecimal(&aggregation.sell_volume_token1),
+            buy_count: aggregation.buy_count,
+            sell_count: aggregation.sell_count,
             block_number: block.number,
             timestamp: timestamp_seconds,
         });
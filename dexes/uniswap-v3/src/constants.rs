@@ -7,3 +7,7 @@ pub const BUCKET_DURATION_SECONDS: u64 = 300;
 
 /// Number of buckets in a 24-hour period (24h / 5min = 288)
 pub const BUCKETS_PER_DAY: u64 = 288;
+
+/// Rolling windows maintained by `store_rolling_deltas`, as `(label, bucket_count)`
+/// pairs over the same 5-minute `store_period_volumes` buckets.
+pub const WINDOWS: &[(&str, u64)] = &[("1h", 12), ("24h", 288), ("7d", 2016)];
@@ -0,0 +1,6 @@
+// ─────────────────────────────────────────────────────────────────────────────
+// Map handlers module
+// ─────────────────────────────────────────────────────────────────────────────
+
+pub mod ohlc_candles;
+pub mod ticker_output;
@@ -0,0 +1,147 @@
+// ─────────────────────────────────────────────────────────────────────────────
+// Standalone decimal-adjusted OHLC candle mapper
+// ─────────────────────────────────────────────────────────────────────────────
+
+use crate::constants::BUCKET_DURATION_SECONDS;
+use crate::pb::dex::common::v1::{DexOutput, PoolCandle};
+use crate::pb::uniswap::types::v1::events::pool_event;
+use crate::pb::uniswap::types::v1::Events;
+use dex_common::price_from_sqrt_x96;
+use std::collections::HashSet;
+use std::str::FromStr;
+use substreams::scalar::BigInt;
+use substreams::store::{StoreGet, StoreGetBigDecimal, StoreGetInt64};
+use substreams_ethereum::pb::eth::v2 as eth;
+
+/// Decimal precision of the OHLC strings emitted by [`price_from_sqrt_x96`].
+const CANDLE_PRICE_PRECISION: u32 = 18;
+
+/// Ensure address has 0x prefix
+fn ensure_0x_prefix(address: &str) -> String {
+    if address.starts_with("0x") || address.starts_with("0X") {
+        address.to_string()
+    } else {
+        format!("0x{}", address)
+    }
+}
+
+/// Read a pool's decimal-adjusted OHLC candle for `{pool_address}:{period}`,
+/// decimal-adjusting each raw `sqrtPriceX96` sub-store with full integer
+/// precision via [`price_from_sqrt_x96`]. Returns `None` if the bucket has no
+/// recorded swaps (open is unset) or the pool's decimals aren't registered
+/// yet in `decimals_store`.
+#[allow(clippy::too_many_arguments)]
+fn read_decimal_candle(
+    pool_address: &str,
+    period: u64,
+    sqrt_open_store: &StoreGetBigDecimal,
+    sqrt_high_store: &StoreGetBigDecimal,
+    sqrt_low_store: &StoreGetBigDecimal,
+    sqrt_close_store: &StoreGetBigDecimal,
+    swap_count_store: &StoreGetInt64,
+    decimals_store: &StoreGetInt64,
+    finalized: bool,
+) -> Option<PoolCandle> {
+    let key = format!("{pool_address}:{period}");
+    let open_sqrt = sqrt_open_store.get_last(&key)?;
+
+    let decimals0 = decimals_store
+        .get_last(format!("{pool_address}:d0"))?
+        .max(0) as u32;
+    let decimals1 = decimals_store
+        .get_last(format!("{pool_address}:d1"))?
+        .max(0) as u32;
+
+    let adjust = |sqrt_price: &str| -> String {
+        BigInt::from_str(sqrt_price)
+            .map(|sqrt_price| {
+                price_from_sqrt_x96(&sqrt_price, decimals0, decimals1, CANDLE_PRICE_PRECISION)
+            })
+            .unwrap_or_else(|_| "0".to_string())
+    };
+
+    Some(PoolCandle {
+        pool_address: ensure_0x_prefix(pool_address),
+        period_start_timestamp: period * BUCKET_DURATION_SECONDS,
+        open: adjust(&open_sqrt.to_string()),
+        high: adjust(&sqrt_high_store.get_last(&key).unwrap_or_default().to_string()),
+        low: adjust(&sqrt_low_store.get_last(&key).unwrap_or_default().to_string()),
+        close: adjust(&sqrt_close_store.get_last(&key).unwrap_or_default().to_string()),
+        swap_count: swap_count_store.get_last(&key).unwrap_or_default() as u32,
+        finalized,
+    })
+}
+
+/// Map handler that emits decimal-adjusted OHLC candles as a standalone
+/// output, independent of [`crate::mappers::ticker_output::map_uniswap_ticker_output`]'s
+/// candles (which trade decimal precision for not depending on a pool
+/// decimals registry). Reuses the shared `dex.common.v1.DexOutput.candles`
+/// field, leaving `pools_created` and `tickers` empty.
+#[substreams::handlers::map]
+pub fn map_ohlc_candles(
+    block: eth::Block,
+    events: Events,
+    sqrt_open_store: StoreGetBigDecimal,
+    sqrt_high_store: StoreGetBigDecimal,
+    sqrt_low_store: StoreGetBigDecimal,
+    sqrt_close_store: StoreGetBigDecimal,
+    swap_count_store: StoreGetInt64,
+    pool_decimals_store: StoreGetInt64,
+) -> Result<DexOutput, substreams::errors::Error> {
+    let timestamp_seconds = block
+        .header
+        .as_ref()
+        .and_then(|header| header.timestamp.as_ref())
+        .map(|timestamp| timestamp.seconds)
+        .unwrap_or(0) as u64;
+
+    let period = timestamp_seconds / BUCKET_DURATION_SECONDS;
+
+    // Pools that traded this block, the only ones whose current/previous
+    // bucket can possibly have just closed or be open.
+    let mut pool_addresses: HashSet<&str> = HashSet::new();
+    for event in &events.pool_events {
+        if let Some(pool_event::Type::Swap(_)) = &event.r#type {
+            pool_addresses.insert(&event.pool_address);
+        }
+    }
+
+    let mut candles = vec![];
+    for pool_address in pool_addresses {
+        if let Some(candle) = read_decimal_candle(
+            pool_address,
+            period,
+            &sqrt_open_store,
+            &sqrt_high_store,
+            &sqrt_low_store,
+            &sqrt_close_store,
+            &swap_count_store,
+            &pool_decimals_store,
+            false,
+        ) {
+            candles.push(candle);
+        }
+
+        if period > 0 {
+            if let Some(candle) = read_decimal_candle(
+                pool_address,
+                period - 1,
+                &sqrt_open_store,
+                &sqrt_high_store,
+                &sqrt_low_store,
+                &sqrt_close_store,
+                &swap_count_store,
+                &pool_decimals_store,
+                true,
+            ) {
+                candles.push(candle);
+            }
+        }
+    }
+
+    Ok(DexOutput {
+        pools_created: vec![],
+        tickers: vec![],
+        candles,
+    })
+}
@@ -2,16 +2,21 @@
 // Final ticker output mapper
 // ─────────────────────────────────────────────────────────────────────────────
 
-use crate::pb::dex::common::v1::{DexOutput, PoolCreated, PoolTicker};
+use crate::constants::BUCKET_DURATION_SECONDS;
+use crate::pb::dex::common::v1::{DexOutput, PoolCandle, PoolCreated, PoolTicker};
 use crate::pb::uniswap::types::v1::events::pool_event;
 use crate::pb::uniswap::types::v1::{Events, Pools};
 use crate::utils::format_bigdecimal;
+use dex_common::{format_bigdecimal_encoded, price_from_sqrt_x96, NumericEncoding};
 use std::collections::HashMap;
 use std::str::FromStr;
-use substreams::scalar::BigDecimal;
-use substreams::store::{StoreGet, StoreGetBigDecimal};
+use substreams::scalar::{BigDecimal, BigInt};
+use substreams::store::{StoreGet, StoreGetBigDecimal, StoreGetInt64};
 use substreams_ethereum::pb::eth::v2 as eth;
 
+/// Decimal precision of the `close_price` string emitted by [`price_from_sqrt_x96`].
+const CLOSE_PRICE_PRECISION: u32 = 18;
+
 /// Ensure address has 0x prefix
 fn ensure_0x_prefix(address: &str) -> String {
     if address.starts_with("0x") || address.starts_with("0X") {
@@ -21,42 +26,78 @@ fn ensure_0x_prefix(address: &str) -> String {
     }
 }
 
-/// Calculate price from Uniswap V3 sqrtPriceX96
-/// sqrtPriceX96 represents sqrt(price) * 2^96
-/// Returns the price as token1/token0
-fn calculate_price_from_sqrt(sqrt_price_str: &str) -> String {
-    if sqrt_price_str.is_empty() || sqrt_price_str == "0" {
-        return "0".to_string();
-    }
+/// Read a candle's four OHLC sub-stores plus swap count for `{pool_address}:{period}`.
+/// Returns `None` if the bucket has no recorded swaps (open is unset).
+fn read_candle(
+    pool_address: &str,
+    period: u64,
+    open_store: &StoreGetBigDecimal,
+    high_store: &StoreGetBigDecimal,
+    low_store: &StoreGetBigDecimal,
+    close_store: &StoreGetBigDecimal,
+    swap_count_store: &StoreGetInt64,
+    finalized: bool,
+) -> Option<PoolCandle> {
+    let key = format!("{pool_address}:{period}");
+    let open = open_store.get_last(&key)?;
 
-    match BigDecimal::from_str(sqrt_price_str) {
-        Ok(sqrt_price_x96) => {
-            // Calculate price = (sqrtPriceX96 / 2^96)^2
-            let two_96 = BigDecimal::from_str("79228162514264337593543950336").unwrap(); // 2^96
-            let sqrt_price = sqrt_price_x96 / two_96;
-            let price = sqrt_price.clone() * sqrt_price;
-            format_bigdecimal(&price)
-        }
-        Err(_) => "0".to_string(),
-    }
+    Some(PoolCandle {
+        pool_address: ensure_0x_prefix(pool_address),
+        period_start_timestamp: period * BUCKET_DURATION_SECONDS,
+        open: format_bigdecimal(&open),
+        high: format_bigdecimal(&high_store.get_last(&key).unwrap_or_default()),
+        low: format_bigdecimal(&low_store.get_last(&key).unwrap_or_default()),
+        close: format_bigdecimal(&close_store.get_last(&key).unwrap_or_default()),
+        swap_count: swap_count_store.get_last(&key).unwrap_or_default() as u32,
+        finalized,
+    })
 }
 
-/// Map handler that generates the final DexOutput with ticker information
-/// Combines current block swap data with 24h rolling volumes
+/// Map handler that generates the final DexOutput with ticker and candle information
+/// Combines current block swap data with 24h rolling volumes and per-bucket OHLC candles.
+///
+/// `params` optionally selects the numeric output encoding via `encoding=hex`
+/// (default `encoding=decimal`), applied to `block_volume_token0/1` and
+/// `volume_24h_token0/1`.
 #[substreams::handlers::map]
 pub fn map_uniswap_ticker_output(
+    params: String,
     block: eth::Block,
     pools: Pools,
     events: Events,
     rolling_volumes_store: StoreGetBigDecimal,
+    period_open_store: StoreGetBigDecimal,
+    period_high_store: StoreGetBigDecimal,
+    period_low_store: StoreGetBigDecimal,
+    period_close_store: StoreGetBigDecimal,
+    period_swap_count_store: StoreGetInt64,
+    liquidity_deltas_store: StoreGetBigDecimal,
+    period_fees_store: StoreGetBigDecimal,
 ) -> Result<DexOutput, substreams::errors::Error> {
+    let encoding = NumericEncoding::from_params(&params);
     let mut dex_output = DexOutput {
         pools_created: vec![],
         tickers: vec![],
+        candles: vec![],
     };
 
-    // Pass through pool creation events
+    // Pass through pool creation events, tracking each pool's decimals along
+    // the way so the close price below can be decimal-adjusted.
+    let mut decimals_by_pool: HashMap<String, (u32, u32)> = HashMap::new();
+
     for pool in pools.pools {
+        let token0_decimals = pool
+            .token0
+            .as_ref()
+            .map(|token| token.decimals as u32)
+            .unwrap_or(0);
+        let token1_decimals = pool
+            .token1
+            .as_ref()
+            .map(|token| token.decimals as u32)
+            .unwrap_or(0);
+        decimals_by_pool.insert(pool.address.clone(), (token0_decimals, token1_decimals));
+
         dex_output.pools_created.push(PoolCreated {
             pool_address: ensure_0x_prefix(&pool.address),
             token0: pool
@@ -72,16 +113,8 @@ pub fn map_uniswap_ticker_output(
             fee: pool.fee_tier.parse::<u32>().unwrap_or_default(),
             block_number: block.number,
             transaction_hash: ensure_0x_prefix(&pool.transaction_id),
-            token0_decimals: pool
-                .token0
-                .as_ref()
-                .map(|token| token.decimals as u32)
-                .unwrap_or(0),
-            token1_decimals: pool
-                .token1
-                .as_ref()
-                .map(|token| token.decimals as u32)
-                .unwrap_or(0),
+            token0_decimals,
+            token1_decimals,
         });
     }
 
@@ -89,26 +122,43 @@ pub fn map_uniswap_ticker_output(
     let mut pool_aggregations: HashMap<String, (BigDecimal, BigDecimal, u32, String)> =
         HashMap::new();
 
+    // Mint/Burn events seen this block, per pool. Counted separately from
+    // swaps since a pool can receive liquidity without trading in the same
+    // block.
+    let mut liquidity_event_counts: HashMap<String, u32> = HashMap::new();
+
     for event in events.pool_events {
-        if let Some(pool_event::Type::Swap(swap_event)) = event.r#type {
-            let entry = pool_aggregations
-                .entry(event.pool_address.clone())
-                .or_insert((BigDecimal::zero(), BigDecimal::zero(), 0, String::new()));
-
-            // Accumulate token0 volume
-            if let Ok(volume) = BigDecimal::from_str(swap_event.amount_0.trim_start_matches('-')) {
-                entry.0 = entry.0.clone() + volume;
-            }
+        match event.r#type {
+            Some(pool_event::Type::Swap(swap_event)) => {
+                let entry = pool_aggregations
+                    .entry(event.pool_address.clone())
+                    .or_insert((BigDecimal::zero(), BigDecimal::zero(), 0, String::new()));
 
-            // Accumulate token1 volume
-            if let Ok(volume) = BigDecimal::from_str(swap_event.amount_1.trim_start_matches('-')) {
-                entry.1 = entry.1.clone() + volume;
-            }
+                // Accumulate token0 volume
+                if let Ok(volume) =
+                    BigDecimal::from_str(swap_event.amount_0.trim_start_matches('-'))
+                {
+                    entry.0 = entry.0.clone() + volume;
+                }
 
-            entry.2 += 1; // Increment swap count
+                // Accumulate token1 volume
+                if let Ok(volume) =
+                    BigDecimal::from_str(swap_event.amount_1.trim_start_matches('-'))
+                {
+                    entry.1 = entry.1.clone() + volume;
+                }
 
-            // Update the last sqrt_price for this pool (closing price)
-            entry.3 = swap_event.sqrt_price.clone();
+                entry.2 += 1; // Increment swap count
+
+                // Update the last sqrt_price for this pool (closing price)
+                entry.3 = swap_event.sqrt_price.clone();
+            }
+            Some(pool_event::Type::Mint(_)) | Some(pool_event::Type::Burn(_)) => {
+                *liquidity_event_counts
+                    .entry(event.pool_address.clone())
+                    .or_insert(0) += 1;
+            }
+            _ => {}
         }
     }
 
@@ -119,30 +169,111 @@ pub fn map_uniswap_ticker_output(
         .map(|timestamp| timestamp.seconds)
         .unwrap_or(0) as u64;
 
+    let period = timestamp_seconds / BUCKET_DURATION_SECONDS;
+
     // Generate ticker data for each pool that had swaps
     for (pool_address, (current_volume_token0, current_volume_token1, swaps, last_sqrt_price)) in
-        pool_aggregations
+        &pool_aggregations
     {
-        // Fetch 24h rolling volumes from store
+        // Fetch 24h rolling volumes from store (store_rolling_deltas also
+        // maintains 1h/7d windows under the same pool address, for consumers
+        // that need a different horizon than this ticker's `volume_24h_*`).
         let rolling_volume_token0 = rolling_volumes_store
-            .get_last(format!("{pool_address}:t0"))
+            .get_last(format!("{pool_address}:24h:t0"))
             .unwrap_or_default();
         let rolling_volume_token1 = rolling_volumes_store
-            .get_last(format!("{pool_address}:t1"))
+            .get_last(format!("{pool_address}:24h:t1"))
+            .unwrap_or_default();
+
+        // Decimal-adjusted close price, derived from sqrtPriceX96 with
+        // full-precision integer arithmetic (falls back to "0" for a pool
+        // whose PoolCreated event wasn't seen this block).
+        let close_price = decimals_by_pool
+            .get(pool_address)
+            .and_then(|(decimals0, decimals1)| {
+                BigInt::from_str(last_sqrt_price)
+                    .ok()
+                    .map(|sqrt_price| (sqrt_price, *decimals0, *decimals1))
+            })
+            .map(|(sqrt_price, decimals0, decimals1)| {
+                price_from_sqrt_x96(&sqrt_price, decimals0, decimals1, CLOSE_PRICE_PRECISION)
+            })
+            .unwrap_or_else(|| "0".to_string());
+
+        // Net liquidity change since genesis, from Mint (+) / Burn (-)
+        // amounts. Lets consumers catch migrations and rug-pull-style
+        // withdrawals that pure swap volume can't reveal, and keeps the
+        // pool's effective depth between this and reserve/TVL estimates.
+        let net_liquidity_token0 = liquidity_deltas_store
+            .get_last(format!("{pool_address}:liq0"))
+            .unwrap_or_default();
+        let net_liquidity_token1 = liquidity_deltas_store
+            .get_last(format!("{pool_address}:liq1"))
+            .unwrap_or_default();
+
+        // Fee revenue accrued this bucket, from `store_period_fees`
+        // (`input_amount * fee_tier / 1_000_000` per swap, input side only).
+        let fees_token0 = period_fees_store
+            .get_last(format!("{pool_address}:{period}:fee0"))
+            .unwrap_or_default();
+        let fees_token1 = period_fees_store
+            .get_last(format!("{pool_address}:{period}:fee1"))
             .unwrap_or_default();
 
         dex_output.tickers.push(PoolTicker {
-            pool_address: ensure_0x_prefix(&pool_address),
-            block_volume_token0: format_bigdecimal(&current_volume_token0),
-            block_volume_token1: format_bigdecimal(&current_volume_token1),
-            swap_count: swaps,
-            close_price: calculate_price_from_sqrt(&last_sqrt_price),
-            volume_24h_token0: format_bigdecimal(&rolling_volume_token0),
-            volume_24h_token1: format_bigdecimal(&rolling_volume_token1),
+            pool_address: ensure_0x_prefix(pool_address),
+            block_volume_token0: format_bigdecimal_encoded(current_volume_token0, encoding),
+            block_volume_token1: format_bigdecimal_encoded(current_volume_token1, encoding),
+            swap_count: *swaps,
+            close_price,
+            volume_24h_token0: format_bigdecimal_encoded(&rolling_volume_token0, encoding),
+            volume_24h_token1: format_bigdecimal_encoded(&rolling_volume_token1, encoding),
+            liquidity_events_count: liquidity_event_counts
+                .get(pool_address)
+                .copied()
+                .unwrap_or(0),
+            net_liquidity_token0: format_bigdecimal(&net_liquidity_token0),
+            net_liquidity_token1: format_bigdecimal(&net_liquidity_token1),
+            fees_token0: format_bigdecimal(&fees_token0),
+            fees_token1: format_bigdecimal(&fees_token1),
             block_number: block.number,
             timestamp: timestamp_seconds,
         });
     }
 
+    // Emit the still-open candle for the current bucket, plus the previous
+    // bucket's candle (now finalized, since its time window has fully elapsed),
+    // for every pool that trades in this block. The previous candle is
+    // re-emitted in each such block of the current period, not only the first.
+    for pool_address in pool_aggregations.keys() {
+        if let Some(candle) = read_candle(
+            pool_address,
+            period,
+            &period_open_store,
+            &period_high_store,
+            &period_low_store,
+            &period_close_store,
+            &period_swap_count_store,
+            false,
+        ) {
+            dex_output.candles.push(candle);
+        }
+
+        if period > 0 {
+            if let Some(candle) = read_candle(
+                pool_address,
+                period - 1,
+                &period_open_store,
+                &period_high_store,
+                &period_low_store,
+                &period_close_store,
+                &period_swap_count_store,
+                true,
+            ) {
+                dex_output.candles.push(candle);
+            }
+        }
+    }
+
     Ok(dex_output)
 }
@@ -0,0 +1,43 @@
+// ─────────────────────────────────────────────────────────────────────────────
+// Mint/Burn net-liquidity accumulator
+// ─────────────────────────────────────────────────────────────────────────────
+
+use crate::pb::uniswap::types::v1::events::pool_event;
+use crate::pb::uniswap::types::v1::Events;
+use std::str::FromStr;
+use substreams::scalar::BigDecimal;
+use substreams::store::{StoreAdd, StoreAddBigDecimal, StoreNew};
+
+/// Store handler that accumulates signed liquidity-provision amounts into a
+/// running per-pool total, keyed `{pool_address}:liq0`/`:liq1`. `Mint` adds
+/// `amount0`/`amount1` (liquidity entering the pool); `Burn` subtracts them
+/// (liquidity leaving). Unlike swap volume, this nets to the pool's lifetime
+/// liquidity change rather than a turnover total, so migrations and
+/// withdrawals show up as negative deltas instead of being folded into
+/// unsigned volume.
+#[substreams::handlers::store]
+pub fn store_liquidity_deltas(events: Events, store: StoreAddBigDecimal) {
+    for event in events.pool_events {
+        let pool_address = &event.pool_address;
+
+        match event.r#type {
+            Some(pool_event::Type::Mint(mint_event)) => {
+                if let Ok(amount0) = BigDecimal::from_str(&mint_event.amount_0) {
+                    store.add(0, format!("{pool_address}:liq0"), amount0);
+                }
+                if let Ok(amount1) = BigDecimal::from_str(&mint_event.amount_1) {
+                    store.add(0, format!("{pool_address}:liq1"), amount1);
+                }
+            }
+            Some(pool_event::Type::Burn(burn_event)) => {
+                if let Ok(amount0) = BigDecimal::from_str(&burn_event.amount_0) {
+                    store.add(0, format!("{pool_address}:liq0"), amount0.neg());
+                }
+                if let Ok(amount1) = BigDecimal::from_str(&burn_event.amount_1) {
+                    store.add(0, format!("{pool_address}:liq1"), amount1.neg());
+                }
+            }
+            _ => {}
+        }
+    }
+}
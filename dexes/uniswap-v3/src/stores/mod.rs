@@ -2,8 +2,22 @@
 // Store handlers module
 // ─────────────────────────────────────────────────────────────────────────────
 
+pub mod liquidity_deltas;
+pub mod period_fees;
+pub mod period_ohlc;
 pub mod period_volumes;
+pub mod pool_decimals;
+pub mod pool_fee_tier;
 pub mod rolling_deltas;
 
+pub use liquidity_deltas::store_liquidity_deltas;
+pub use period_fees::store_period_fees;
+pub use period_ohlc::{
+    store_period_close, store_period_high, store_period_low, store_period_open,
+    store_period_sqrt_close, store_period_sqrt_high, store_period_sqrt_low,
+    store_period_sqrt_open, store_period_swap_count,
+};
 pub use period_volumes::store_period_volumes;
+pub use pool_decimals::store_pool_decimals;
+pub use pool_fee_tier::store_pool_fee_tier;
 pub use rolling_deltas::store_rolling_deltas;
@@ -0,0 +1,75 @@
+// ─────────────────────────────────────────────────────────────────────────────
+// Five-minute bucket fee-revenue accumulator
+// ─────────────────────────────────────────────────────────────────────────────
+
+use crate::constants::BUCKET_DURATION_SECONDS;
+use crate::pb::uniswap::types::v1::events::pool_event;
+use crate::pb::uniswap::types::v1::Events;
+use std::str::FromStr;
+use substreams::scalar::BigDecimal;
+use substreams::store::{StoreAdd, StoreAddBigDecimal, StoreGet, StoreGetInt64, StoreNew};
+use substreams_ethereum::pb::eth::v2 as eth;
+
+/// Store handler that accumulates each swap's fee revenue into the same
+/// 5-minute buckets `store_period_volumes` uses, keyed
+/// `{pool_address}:{period}:fee0`/`:fee1`. Fee revenue is `input_amount *
+/// fee_tier / 1_000_000` (a 0.3% pool has `fee_tier = 3000`), computed in
+/// BigDecimal to stay precise for fractional-token fee amounts. Only the
+/// fee-bearing input side of the swap is counted — the positive of
+/// `amount_0`/`amount_1` — since the fee is charged on what flows in, not on
+/// what flows out.
+#[substreams::handlers::store]
+pub fn store_period_fees(
+    block: eth::Block,
+    events: Events,
+    fee_tier_store: StoreGetInt64,
+    store: StoreAddBigDecimal,
+) {
+    let timestamp_seconds = block
+        .header
+        .as_ref()
+        .and_then(|header| header.timestamp.as_ref())
+        .map(|timestamp| timestamp.seconds)
+        .unwrap_or(0) as u64;
+
+    let period = timestamp_seconds / BUCKET_DURATION_SECONDS;
+
+    for event in events.pool_events {
+        if let Some(pool_event::Type::Swap(swap_event)) = event.r#type {
+            let pool_address = &event.pool_address;
+
+            let Some(fee_tier) = fee_tier_store.get_last(pool_address) else {
+                continue;
+            };
+            if fee_tier == 0 {
+                continue;
+            }
+            let fee_fraction = BigDecimal::from_str(&fee_tier.to_string()).unwrap_or_default()
+                / BigDecimal::from_str("1000000").unwrap();
+
+            if !swap_event.amount_0.starts_with('-') {
+                if let Ok(amount0) =
+                    BigDecimal::from_str(swap_event.amount_0.trim_start_matches('-'))
+                {
+                    store.add(
+                        0,
+                        format!("{pool_address}:{period}:fee0"),
+                        amount0 * fee_fraction.clone(),
+                    );
+                }
+            }
+
+            if !swap_event.amount_1.starts_with('-') {
+                if let Ok(amount1) =
+                    BigDecimal::from_str(swap_event.amount_1.trim_start_matches('-'))
+                {
+                    store.add(
+                        0,
+                        format!("{pool_address}:{period}:fee1"),
+                        amount1 * fee_fraction.clone(),
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,151 @@
+// ─────────────────────────────────────────────────────────────────────────────
+// Per-bucket OHLC accumulator
+// ─────────────────────────────────────────────────────────────────────────────
+
+use crate::constants::BUCKET_DURATION_SECONDS;
+use crate::pb::uniswap::types::v1::events::pool_event;
+use crate::pb::uniswap::types::v1::Events;
+use crate::utils::price_from_sqrt_price;
+use std::str::FromStr;
+use substreams::scalar::BigDecimal;
+use substreams::store::{
+    StoreAdd, StoreAddInt64, StoreMax, StoreMaxBigDecimal, StoreMin, StoreMinBigDecimal, StoreNew,
+    StoreSet, StoreSetBigDecimal, StoreSetIfNotExists, StoreSetIfNotExistsBigDecimal,
+};
+use substreams_ethereum::pb::eth::v2 as eth;
+
+/// Store handler that records the first swap price of each 5-minute bucket as
+/// the candle's `open`. Uses `set_if_not_exists` so only the first write per
+/// bucket sticks, regardless of how many blocks the bucket spans.
+#[substreams::handlers::store]
+pub fn store_period_open(block: eth::Block, events: Events, store: StoreSetIfNotExistsBigDecimal) {
+    for_each_swap_price(&block, &events, |pool_address, period, price| {
+        store.set_if_not_exists(0, format!("{pool_address}:{period}"), &price);
+    });
+}
+
+/// Store handler that tracks the running high (max) price of each bucket.
+#[substreams::handlers::store]
+pub fn store_period_high(block: eth::Block, events: Events, store: StoreMaxBigDecimal) {
+    for_each_swap_price(&block, &events, |pool_address, period, price| {
+        store.max(0, format!("{pool_address}:{period}"), price);
+    });
+}
+
+/// Store handler that tracks the running low (min) price of each bucket.
+#[substreams::handlers::store]
+pub fn store_period_low(block: eth::Block, events: Events, store: StoreMinBigDecimal) {
+    for_each_swap_price(&block, &events, |pool_address, period, price| {
+        store.min(0, format!("{pool_address}:{period}"), price);
+    });
+}
+
+/// Store handler that records the last swap price of each bucket as the
+/// candle's `close`. Because swaps within a block are processed in order and
+/// later blocks overwrite earlier ones, the final write for a bucket is
+/// always its true close.
+#[substreams::handlers::store]
+pub fn store_period_close(block: eth::Block, events: Events, store: StoreSetBigDecimal) {
+    for_each_swap_price(&block, &events, |pool_address, period, price| {
+        store.set(0, format!("{pool_address}:{period}"), &price);
+    });
+}
+
+/// Store handler that counts swaps per bucket, so candles can report activity
+/// without clients re-deriving it from volume.
+#[substreams::handlers::store]
+pub fn store_period_swap_count(block: eth::Block, events: Events, store: StoreAddInt64) {
+    for_each_swap_price(&block, &events, |pool_address, period, _price| {
+        store.add(0, format!("{pool_address}:{period}"), 1);
+    });
+}
+
+/// Iterate swap events in block order, deriving each one's price and bucket,
+/// and hand it to `callback`. Shared by all OHLC sub-stores so the bucketing
+/// logic lives in exactly one place.
+fn for_each_swap_price(block: &eth::Block, events: &Events, mut callback: impl FnMut(&str, u64, BigDecimal)) {
+    let timestamp_seconds = block
+        .header
+        .as_ref()
+        .and_then(|header| header.timestamp.as_ref())
+        .map(|timestamp| timestamp.seconds)
+        .unwrap_or(0) as u64;
+
+    let period = timestamp_seconds / BUCKET_DURATION_SECONDS;
+
+    for event in &events.pool_events {
+        if let Some(pool_event::Type::Swap(swap_event)) = &event.r#type {
+            if let Ok(price) = price_from_sqrt_price(&swap_event.sqrt_price) {
+                callback(&event.pool_address, period, price);
+            }
+        }
+    }
+}
+
+/// Store handler that records the first swap's raw `sqrtPriceX96` of each
+/// bucket. Kept separate from [`store_period_open`], which stores the
+/// lossily-converted ratio: [`crate::mappers::ohlc_candles::map_ohlc_candles`]
+/// needs the raw value so it can decimal-adjust with full integer precision
+/// via `dex_common::price_from_sqrt_x96`.
+#[substreams::handlers::store]
+pub fn store_period_sqrt_open(
+    block: eth::Block,
+    events: Events,
+    store: StoreSetIfNotExistsBigDecimal,
+) {
+    for_each_swap_sqrt_price(&block, &events, |pool_address, period, sqrt_price| {
+        store.set_if_not_exists(0, format!("{pool_address}:{period}"), &sqrt_price);
+    });
+}
+
+/// Store handler that tracks the running high (max) raw `sqrtPriceX96` of
+/// each bucket.
+#[substreams::handlers::store]
+pub fn store_period_sqrt_high(block: eth::Block, events: Events, store: StoreMaxBigDecimal) {
+    for_each_swap_sqrt_price(&block, &events, |pool_address, period, sqrt_price| {
+        store.max(0, format!("{pool_address}:{period}"), sqrt_price);
+    });
+}
+
+/// Store handler that tracks the running low (min) raw `sqrtPriceX96` of each
+/// bucket.
+#[substreams::handlers::store]
+pub fn store_period_sqrt_low(block: eth::Block, events: Events, store: StoreMinBigDecimal) {
+    for_each_swap_sqrt_price(&block, &events, |pool_address, period, sqrt_price| {
+        store.min(0, format!("{pool_address}:{period}"), sqrt_price);
+    });
+}
+
+/// Store handler that records the last swap's raw `sqrtPriceX96` of each
+/// bucket.
+#[substreams::handlers::store]
+pub fn store_period_sqrt_close(block: eth::Block, events: Events, store: StoreSetBigDecimal) {
+    for_each_swap_sqrt_price(&block, &events, |pool_address, period, sqrt_price| {
+        store.set(0, format!("{pool_address}:{period}"), &sqrt_price);
+    });
+}
+
+/// Like [`for_each_swap_price`], but hands the raw `sqrtPriceX96` to
+/// `callback` instead of the converted price ratio.
+fn for_each_swap_sqrt_price(
+    block: &eth::Block,
+    events: &Events,
+    mut callback: impl FnMut(&str, u64, BigDecimal),
+) {
+    let timestamp_seconds = block
+        .header
+        .as_ref()
+        .and_then(|header| header.timestamp.as_ref())
+        .map(|timestamp| timestamp.seconds)
+        .unwrap_or(0) as u64;
+
+    let period = timestamp_seconds / BUCKET_DURATION_SECONDS;
+
+    for event in &events.pool_events {
+        if let Some(pool_event::Type::Swap(swap_event)) = &event.r#type {
+            if let Ok(sqrt_price) = BigDecimal::from_str(&swap_event.sqrt_price) {
+                callback(&event.pool_address, period, sqrt_price);
+            }
+        }
+    }
+}
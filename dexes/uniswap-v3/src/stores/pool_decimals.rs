@@ -0,0 +1,29 @@
+// ─────────────────────────────────────────────────────────────────────────────
+// Persistent pool token-decimals registry
+// ─────────────────────────────────────────────────────────────────────────────
+
+use crate::pb::uniswap::types::v1::Pools;
+use substreams::store::{StoreNew, StoreSetIfNotExists, StoreSetIfNotExistsInt64};
+
+/// Store handler that records each pool's token decimals the first time its
+/// `PoolCreated` event is seen. Unlike the per-block `Pools` input, this
+/// persists across blocks, so later candles can be decimal-adjusted even for
+/// pools created well before the block being processed.
+#[substreams::handlers::store]
+pub fn store_pool_decimals(pools: Pools, store: StoreSetIfNotExistsInt64) {
+    for pool in pools.pools {
+        let token0_decimals = pool
+            .token0
+            .as_ref()
+            .map(|token| token.decimals as i64)
+            .unwrap_or(0);
+        let token1_decimals = pool
+            .token1
+            .as_ref()
+            .map(|token| token.decimals as i64)
+            .unwrap_or(0);
+
+        store.set_if_not_exists(0, format!("{}:d0", pool.address), token0_decimals);
+        store.set_if_not_exists(0, format!("{}:d1", pool.address), token1_decimals);
+    }
+}
@@ -0,0 +1,18 @@
+// ─────────────────────────────────────────────────────────────────────────────
+// Persistent pool fee-tier registry
+// ─────────────────────────────────────────────────────────────────────────────
+
+use crate::pb::uniswap::types::v1::Pools;
+use substreams::store::{StoreNew, StoreSetIfNotExists, StoreSetIfNotExistsInt64};
+
+/// Store handler that records each pool's fee tier (in hundredths of a bip,
+/// e.g. `3000` for 0.3%) the first time its `PoolCreated` event is seen, so
+/// `store_period_fees` can thread it through to swaps without re-deriving it
+/// from a per-block `Pools` input it may not have.
+#[substreams::handlers::store]
+pub fn store_pool_fee_tier(pools: Pools, store: StoreSetIfNotExistsInt64) {
+    for pool in pools.pools {
+        let fee_tier = pool.fee_tier.parse::<i64>().unwrap_or(0);
+        store.set_if_not_exists(0, &pool.address, fee_tier);
+    }
+}
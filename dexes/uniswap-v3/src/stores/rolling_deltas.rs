@@ -1,8 +1,8 @@
 // ─────────────────────────────────────────────────────────────────────────────
-// Rolling 24h volume calculator with O(1) performance
+// Rolling multi-window volume calculator with O(1) performance
 // ─────────────────────────────────────────────────────────────────────────────
 
-use crate::constants::{BUCKETS_PER_DAY, BUCKET_DURATION_SECONDS};
+use crate::constants::{BUCKET_DURATION_SECONDS, WINDOWS};
 use crate::pb::uniswap::types::v1::events::pool_event;
 use crate::pb::uniswap::types::v1::Events;
 use crate::utils::is_zero;
@@ -12,9 +12,10 @@ use substreams::scalar::BigDecimal;
 use substreams::store::{StoreAdd, StoreAddBigDecimal, StoreGet, StoreGetBigDecimal, StoreNew};
 use substreams_ethereum::pb::eth::v2 as eth;
 
-/// Store handler that maintains 24h rolling volume totals
-/// Uses an O(1) algorithm by adding current swap volumes and subtracting
-/// volumes that are exactly 24 hours old (288 periods)
+/// Store handler that maintains rolling volume totals for each window in
+/// [`WINDOWS`] (1h, 24h, 7d). Uses an O(1) algorithm per window by adding the
+/// current period's delta and subtracting the bucket that just fell outside
+/// the window.
 #[substreams::handlers::store]
 pub fn store_rolling_deltas(
     block: eth::Block,
@@ -30,7 +31,6 @@ pub fn store_rolling_deltas(
         .unwrap_or(0) as u64;
 
     let period = timestamp_seconds / BUCKET_DURATION_SECONDS;
-    let evict_period = period.saturating_sub(BUCKETS_PER_DAY);
 
     // Accumulate positive deltas for this block
     let mut pool_volume_deltas: HashMap<String, (BigDecimal, BigDecimal)> = HashMap::new();
@@ -57,41 +57,44 @@ pub fn store_rolling_deltas(
         }
     }
 
-    // For each active pool: subtract old bucket and add new delta
+    // For each active pool and each rolling window: subtract the bucket that
+    // just fell out of the window and add the current period's delta.
     for (pool_address, (delta_token0, delta_token1)) in pool_volume_deltas {
-        // Subtract volumes from exactly 24 hours ago
-        let token0_evict_key = format!("{pool_address}:{evict_period}:t0");
-        let token1_evict_key = format!("{pool_address}:{evict_period}:t1");
+        for (label, bucket_count) in WINDOWS {
+            let evict_period = period.saturating_sub(*bucket_count);
+            let token0_evict_key = format!("{pool_address}:{evict_period}:t0");
+            let token1_evict_key = format!("{pool_address}:{evict_period}:t1");
 
-        let evicted_volume_token0 = period_volumes_store
-            .get_last(&token0_evict_key)
-            .unwrap_or_default();
-        let evicted_volume_token1 = period_volumes_store
-            .get_last(&token1_evict_key)
-            .unwrap_or_default();
+            let evicted_volume_token0 = period_volumes_store
+                .get_last(&token0_evict_key)
+                .unwrap_or_default();
+            let evicted_volume_token1 = period_volumes_store
+                .get_last(&token1_evict_key)
+                .unwrap_or_default();
 
-        // Subtract evicted volumes
-        if !is_zero(&evicted_volume_token0) {
-            rolling_volumes_store.add(
-                0,
-                format!("{pool_address}:t0"),
-                BigDecimal::zero() - evicted_volume_token0,
-            );
-        }
-        if !is_zero(&evicted_volume_token1) {
-            rolling_volumes_store.add(
-                0,
-                format!("{pool_address}:t1"),
-                BigDecimal::zero() - evicted_volume_token1,
-            );
-        }
+            // Subtract evicted volumes
+            if !is_zero(&evicted_volume_token0) {
+                rolling_volumes_store.add(
+                    0,
+                    format!("{pool_address}:{label}:t0"),
+                    BigDecimal::zero() - evicted_volume_token0,
+                );
+            }
+            if !is_zero(&evicted_volume_token1) {
+                rolling_volumes_store.add(
+                    0,
+                    format!("{pool_address}:{label}:t1"),
+                    BigDecimal::zero() - evicted_volume_token1,
+                );
+            }
 
-        // Add current period's delta
-        if !is_zero(&delta_token0) {
-            rolling_volumes_store.add(0, format!("{pool_address}:t0"), delta_token0);
-        }
-        if !is_zero(&delta_token1) {
-            rolling_volumes_store.add(0, format!("{pool_address}:t1"), delta_token1);
+            // Add current period's delta
+            if !is_zero(&delta_token0) {
+                rolling_volumes_store.add(0, format!("{pool_address}:{label}:t0"), delta_token0.clone());
+            }
+            if !is_zero(&delta_token1) {
+                rolling_volumes_store.add(0, format!("{pool_address}:{label}:t1"), delta_token1.clone());
+            }
         }
     }
 }
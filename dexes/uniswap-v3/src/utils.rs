@@ -2,8 +2,22 @@
 // Utility functions for Uniswap V3 substreams
 // ─────────────────────────────────────────────────────────────────────────────
 
+use std::str::FromStr;
 use substreams::scalar::BigDecimal;
 
+/// Calculate price from a Uniswap V3 `sqrtPriceX96` string.
+/// sqrtPriceX96 represents sqrt(price) * 2^96; returns the price as token1/token0.
+pub fn price_from_sqrt_price(sqrt_price_str: &str) -> Result<BigDecimal, ()> {
+    if sqrt_price_str.is_empty() || sqrt_price_str == "0" {
+        return Ok(BigDecimal::zero());
+    }
+
+    let sqrt_price_x96 = BigDecimal::from_str(sqrt_price_str).map_err(|_| ())?;
+    let two_96 = BigDecimal::from_str("79228162514264337593543950336").unwrap(); // 2^96
+    let sqrt_price = sqrt_price_x96 / two_96;
+    Ok(sqrt_price.clone() * sqrt_price)
+}
+
 /// Check if a BigDecimal value is zero
 #[inline]
 pub fn is_zero(big_decimal: &BigDecimal) -> bool {
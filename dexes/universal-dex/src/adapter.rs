@@ -0,0 +1,68 @@
+// ─────────────────────────────────────────────────────────────────────────────
+// Pluggable DEX adapter trait, normalized swap shape, and signature registry
+// ─────────────────────────────────────────────────────────────────────────────
+
+use substreams::scalar::BigInt;
+use substreams_ethereum::block_view::LogView;
+
+/// A swap decoded from a DEX-specific log into a protocol-agnostic shape, so
+/// aggregation doesn't need to know which DEX the log came from.
+#[derive(Clone, Debug)]
+pub struct NormalizedSwap {
+    pub pool_address: Vec<u8>,
+    pub amount0: BigInt,
+    pub amount1: BigInt,
+    /// Post-swap `sqrtPriceX96`, for protocols that emit one (V3). `None` for
+    /// protocols that don't: V2 derives price from a separate `Sync` log, and
+    /// StableSwap pools have no constant-product price at all.
+    pub sqrt_price: Option<BigInt>,
+    /// Post-swap reserves, for protocols that emit them alongside the swap
+    /// itself. Always `None` today: V2's reserves arrive on a separate `Sync`
+    /// log, not the `Swap` log this trait decodes.
+    pub reserves: Option<(BigInt, BigInt)>,
+    /// The traded coin indices, for protocols where `amount0`/`amount1` don't
+    /// always correspond to a fixed token0/token1 pair (StableSwap pools with
+    /// more than two coins). `None` for protocols where token0/token1 is
+    /// always the full pair (V2, V3, Balancer).
+    pub traded_indices: Option<(u32, u32)>,
+}
+
+/// A DEX protocol's swap-log decoder, keyed by the event signature it claims.
+pub trait DexAdapter {
+    /// The `topics[0]` event signature this adapter decodes.
+    fn swap_event_signature(&self) -> [u8; 32];
+
+    /// Decode a log into a [`NormalizedSwap`], or `None` if it doesn't match
+    /// this adapter's expected layout (e.g. too few data bytes).
+    fn decode_swap(&self, log: &LogView) -> Option<NormalizedSwap>;
+}
+
+/// Dispatches a log to whichever registered [`DexAdapter`] owns its
+/// `topics[0]` signature.
+#[derive(Default)]
+pub struct AdapterRegistry {
+    adapters: Vec<Box<dyn DexAdapter>>,
+}
+
+impl AdapterRegistry {
+    pub fn new() -> Self {
+        Self {
+            adapters: Vec::new(),
+        }
+    }
+
+    pub fn register(mut self, adapter: Box<dyn DexAdapter>) -> Self {
+        self.adapters.push(adapter);
+        self
+    }
+
+    /// Decode `log` using whichever registered adapter claims its
+    /// `topics[0]`, if any.
+    pub fn decode(&self, log: &LogView) -> Option<NormalizedSwap> {
+        let topic0 = log.topics().first()?;
+        self.adapters
+            .iter()
+            .find(|adapter| adapter.swap_event_signature().as_slice() == topic0.as_slice())
+            .and_then(|adapter| adapter.decode_swap(log))
+    }
+}
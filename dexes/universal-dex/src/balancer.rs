@@ -0,0 +1,284 @@
+use crate::adapter::{DexAdapter, NormalizedSwap};
+use crate::common::{apply_coin_reserve_delta, SwapAggregation};
+use crate::stableswap::parse_hex_address;
+use dex_common::{calculate_sqrt_price_x96, ensure_0x_prefix, price_from_sqrt_price_x96};
+use std::collections::HashMap;
+use substreams::scalar::BigInt;
+use substreams::store::{StoreAddBigInt, StoreGet, StoreGetBigInt, StoreNew};
+use substreams_ethereum::block_view::LogView;
+use substreams_ethereum::pb::eth::v2 as eth;
+
+// Swap(bytes32 indexed poolId, address indexed tokenIn, address indexed tokenOut, uint256 amountIn, uint256 amountOut)
+// Emitted by the single shared Balancer V2 Vault contract, not per pool.
+pub const BALANCER_SWAP_EVENT_SIG: [u8; 32] =
+    hex_literal::hex!("2170c741c41531aec20e7c107c24eecfdd15e69c9bb0a8dd37b1840b9e0b207b");
+
+/// Per-pool configuration that can't be recovered from a `Swap` log: which of
+/// `tokenIn`/`tokenOut` is this pool's token0/token1, and each one's weight
+/// (in basis points out of 10000), needed for the weighted spot-price formula.
+#[derive(Clone, Copy)]
+pub struct BalancerPoolConfig {
+    pub token0: [u8; 20],
+    pub token1: [u8; 20],
+    pub weight0_bps: u32,
+    pub weight1_bps: u32,
+}
+
+/// Registry of Balancer pool configs, built from the `params` string passed to
+/// `map_balancer_ticker_output` (format:
+/// `poolid:token0:token1:weight0_bps:weight1_bps,...`), keyed by the 32-byte
+/// `poolId` the Vault's `Swap` event carries (there's no per-pool contract
+/// address to key on, unlike V2/V3/StableSwap).
+pub fn parse_registry(params: &str) -> HashMap<Vec<u8>, BalancerPoolConfig> {
+    let mut registry = HashMap::new();
+
+    for entry in params.split(',') {
+        let mut parts = entry.split(':');
+        let (Some(pool_id), Some(token0), Some(token1), Some(weight0_bps), Some(weight1_bps)) = (
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+        ) else {
+            continue;
+        };
+
+        let (Ok(weight0_bps), Ok(weight1_bps)) =
+            (weight0_bps.parse::<u32>(), weight1_bps.parse::<u32>())
+        else {
+            continue;
+        };
+
+        let (Some(pool_id), Some(token0), Some(token1)) = (
+            parse_hex_address(pool_id),
+            parse_hex_address(token0),
+            parse_hex_address(token1),
+        ) else {
+            continue;
+        };
+
+        let (Ok(token0), Ok(token1)) = (token0.try_into(), token1.try_into()) else {
+            continue;
+        };
+
+        registry.insert(
+            pool_id,
+            BalancerPoolConfig {
+                token0,
+                token1,
+                weight0_bps,
+                weight1_bps,
+            },
+        );
+    }
+
+    registry
+}
+
+/// Read a 20-byte address out of the right-aligned 32-byte topic word an
+/// indexed `address` parameter is encoded as.
+fn address_from_topic(topic: &[u8]) -> [u8; 20] {
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&topic[12..32]);
+    address
+}
+
+/// Store handler that reconstructs each registered pool's token0/token1
+/// balances, reorg-safely, as a net flow accumulated from `Swap` deltas: the
+/// in-token's balance goes up by `amountIn`, the out-token's goes down by
+/// `amountOut`. Balancer pools have no `Sync`-style absolute-reserve event
+/// either, so (like `pool_stats::store_pool_lifetime_reserves` for V3) this
+/// starts from zero rather than the pool's true balance at creation — a
+/// directional proxy, not an absolute figure. `process_balancer_event` reads
+/// it back to price swaps off the pool's actual balances instead of the
+/// trade's own amounts.
+#[substreams::handlers::store]
+pub fn store_balancer_reserves(params: String, block: eth::Block, store: StoreAddBigInt) {
+    let registry = parse_registry(&params);
+
+    for log in block.logs() {
+        if log.topics().is_empty() || log.topics()[0] != BALANCER_SWAP_EVENT_SIG {
+            continue;
+        }
+        if log.topics().len() < 4 || log.data().len() < 64 {
+            continue;
+        }
+
+        let pool_id = log.topics()[1].to_vec();
+        let Some(config) = registry.get(&pool_id) else {
+            continue;
+        };
+
+        let token_in = address_from_topic(&log.topics()[2]);
+        let token_out = address_from_topic(&log.topics()[3]);
+        let amount_in = dex_common::uint256_to_bigint(&log.data()[0..32]);
+        let amount_out = dex_common::uint256_to_bigint(&log.data()[32..64]);
+
+        let token_in_is_token0 = token_in == config.token0 && token_out == config.token1;
+        let token_in_is_token1 = token_in == config.token1 && token_out == config.token0;
+        if !token_in_is_token0 && !token_in_is_token1 {
+            continue;
+        }
+
+        let pool_key = format_pool_id(&pool_id);
+        let (coin_in, coin_out) = if token_in_is_token0 {
+            (0u32, 1u32)
+        } else {
+            (1u32, 0u32)
+        };
+        apply_coin_reserve_delta(&store, log.ordinal(), &pool_key, coin_in, amount_in);
+        apply_coin_reserve_delta(
+            &store,
+            log.ordinal(),
+            &pool_key,
+            coin_out,
+            BigInt::zero() - amount_out,
+        );
+    }
+}
+
+/// Process a Balancer V2 weighted-pool `Swap` event and update pool
+/// aggregations, keyed by `poolId` rather than a contract address. The pool's
+/// token0/token1 assignment and weights come from `registry`, since neither
+/// is carried on the event itself. The pool's actual token0/token1 balances
+/// come from `reserves_store` (see [`store_balancer_reserves`]), which
+/// already reflects this trade's own delta.
+pub fn process_balancer_event(
+    log: &LogView,
+    registry: &HashMap<Vec<u8>, BalancerPoolConfig>,
+    reserves_store: &StoreGetBigInt,
+    pool_aggregations: &mut HashMap<Vec<u8>, SwapAggregation>,
+) {
+    // topics: [sig, poolId, tokenIn, tokenOut]; data: amountIn (uint256), amountOut (uint256).
+    if log.topics().len() < 4 || log.data().len() < 64 {
+        return;
+    }
+
+    let pool_id = log.topics()[1].to_vec();
+    let Some(config) = registry.get(&pool_id) else {
+        return;
+    };
+
+    let token_in = address_from_topic(&log.topics()[2]);
+    let token_out = address_from_topic(&log.topics()[3]);
+    let amount_in = dex_common::uint256_to_bigint(&log.data()[0..32]);
+    let amount_out = dex_common::uint256_to_bigint(&log.data()[32..64]);
+
+    let token_in_is_token0 = token_in == config.token0 && token_out == config.token1;
+    let token_in_is_token1 = token_in == config.token1 && token_out == config.token0;
+    if !token_in_is_token0 && !token_in_is_token1 {
+        return;
+    }
+
+    let entry = pool_aggregations.entry(pool_id).or_default();
+
+    let (volume0, volume1) = if token_in_is_token0 {
+        (amount_in.clone(), amount_out.clone())
+    } else {
+        (amount_out.clone(), amount_in.clone())
+    };
+    entry.volume_token0 = entry.volume_token0.clone() + volume0;
+    entry.volume_token1 = entry.volume_token1.clone() + volume1;
+    entry.swap_count += 1;
+
+    // Weight VWAP by the token1 side of the trade, as the other DEX paths do.
+    let amount1_for_vwap = if token_in_is_token0 {
+        amount_out.clone()
+    } else {
+        amount_in.clone()
+    };
+
+    // The pool's actual live token0/token1 balances, not this trade's own
+    // amountIn/amountOut (which would be economically meaningless — a $10
+    // swap and a $10M swap in the same pool would report wildly different
+    // "prices").
+    let pool_key = format_pool_id(&pool_id);
+    let balance0 = reserves_store
+        .get_last(format!("{pool_key}:coin0"))
+        .unwrap_or_default();
+    let balance1 = reserves_store
+        .get_last(format!("{pool_key}:coin1"))
+        .unwrap_or_default();
+    let (balance_in, balance_out) = if token_in_is_token0 {
+        (balance0, balance1)
+    } else {
+        (balance1, balance0)
+    };
+
+    // num/den = (balanceIn/weightIn) / (balanceOut/weightOut), the out-priced-in-in spot price.
+    let num = balance_in
+        * BigInt::from(if token_in_is_token0 {
+            config.weight1_bps
+        } else {
+            config.weight0_bps
+        });
+    let den = balance_out
+        * BigInt::from(if token_in_is_token0 {
+            config.weight0_bps
+        } else {
+            config.weight1_bps
+        });
+
+    // num/den is already shaped as token1-per-token0 when token1 was the side
+    // swapped in (out-token0-priced-in-in-token1 is exactly that); flip it
+    // when token0 was the side swapped in instead.
+    let (reserve0, reserve1) = if token_in_is_token0 {
+        (num, den)
+    } else {
+        (den, num)
+    };
+
+    if reserve0 > BigInt::zero() && reserve1 > BigInt::zero() {
+        entry.last_sqrt_price = calculate_sqrt_price_x96(&reserve0, &reserve1);
+        let price = price_from_sqrt_price_x96(&entry.last_sqrt_price);
+        entry.observe_price(&price);
+        let volume = substreams::scalar::BigDecimal::from(amount1_for_vwap);
+        entry.observe_vwap_sample(&price, &volume);
+    }
+}
+
+pub fn format_pool_id(pool_id_bytes: &[u8]) -> String {
+    ensure_0x_prefix(&substreams::Hex(pool_id_bytes).to_string())
+}
+
+/// [`DexAdapter`] for Balancer V2 `Swap` events, for the generic unified
+/// router. Unlike [`process_balancer_event`], this doesn't need the pool's
+/// weight/token registry: `NormalizedSwap` only carries volume, and since the
+/// Vault's `Swap` event names `tokenIn`/`tokenOut` by address directly
+/// (unlike StableSwap's opaque coin indices), token0/token1 can be assigned
+/// by address order, the same convention pools are created with on V2/V3.
+pub struct BalancerAdapter;
+
+impl DexAdapter for BalancerAdapter {
+    fn swap_event_signature(&self) -> [u8; 32] {
+        BALANCER_SWAP_EVENT_SIG
+    }
+
+    fn decode_swap(&self, log: &LogView) -> Option<NormalizedSwap> {
+        if log.topics().len() < 4 || log.data().len() < 64 {
+            return None;
+        }
+
+        let pool_id = log.topics()[1].to_vec();
+        let token_in = address_from_topic(&log.topics()[2]);
+        let token_out = address_from_topic(&log.topics()[3]);
+        let amount_in = dex_common::uint256_to_bigint(&log.data()[0..32]);
+        let amount_out = dex_common::uint256_to_bigint(&log.data()[32..64]);
+
+        let (amount0, amount1) = if token_in < token_out {
+            (amount_in, amount_out)
+        } else {
+            (amount_out, amount_in)
+        };
+
+        Some(NormalizedSwap {
+            pool_address: pool_id,
+            amount0,
+            amount1,
+            sqrt_price: None,
+            reserves: None,
+            traded_indices: None,
+        })
+    }
+}
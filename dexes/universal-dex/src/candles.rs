@@ -0,0 +1,406 @@
+// ─────────────────────────────────────────────────────────────────────────────
+// OHLC candlestick stores over configurable time windows
+// ─────────────────────────────────────────────────────────────────────────────
+
+use crate::adapter::{AdapterRegistry, DexAdapter};
+use crate::pb::dex::common::v1::{CandleOutput, DexCandle};
+use crate::v2::UniswapV2Adapter;
+use crate::v3::{PancakeswapV3Adapter, UniswapV3Adapter};
+use dex_common::{
+    calculate_sqrt_price_x96, ensure_0x_prefix, format_bigdecimal, price_from_sqrt_x96,
+    uint112_to_bigint,
+};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use substreams::scalar::{BigDecimal, BigInt};
+use substreams::store::{
+    StoreAdd, StoreAddBigDecimal, StoreAddInt64, StoreGet, StoreGetBigDecimal, StoreGetInt64,
+    StoreGetString, StoreMax, StoreMaxBigDecimal, StoreMin, StoreMinBigDecimal, StoreNew, StoreSet,
+    StoreSetBigDecimal, StoreSetIfNotExists, StoreSetIfNotExistsBigDecimal,
+};
+use substreams::Hex;
+use substreams_ethereum::pb::eth::v2 as eth;
+
+/// Decimal precision of the price string [`price_from_sqrt_x96`] produces,
+/// before it's parsed back into a [`BigDecimal`] for the high/low/open/close
+/// sub-stores below (which need numeric, not string, comparisons).
+const CANDLE_PRICE_PRECISION: u32 = 18;
+
+/// Windows used when `params` is empty or has no recognizable entries.
+const DEFAULT_WINDOWS: &[(&str, u64)] = &[("1m", 60), ("5m", 300), ("1h", 3600)];
+
+/// Parse a comma-separated `params` string like `"1m,5m,1h"` into
+/// `(label, interval_seconds)` pairs (units: `m`inutes, `h`ours, `d`ays).
+/// Falls back to [`DEFAULT_WINDOWS`] if nothing parses.
+pub fn parse_windows(params: &str) -> Vec<(String, u64)> {
+    let parsed: Vec<(String, u64)> = params
+        .split(',')
+        .filter_map(|token| {
+            let token = token.trim();
+            if token.len() < 2 {
+                return None;
+            }
+            let (digits, unit) = token.split_at(token.len() - 1);
+            let count: u64 = digits.parse().ok()?;
+            let seconds = match unit {
+                "m" => count * 60,
+                "h" => count * 3600,
+                "d" => count * 86400,
+                _ => return None,
+            };
+            Some((token.to_string(), seconds))
+        })
+        .collect();
+
+    if parsed.is_empty() {
+        DEFAULT_WINDOWS
+            .iter()
+            .map(|(label, seconds)| (label.to_string(), *seconds))
+            .collect()
+    } else {
+        parsed
+    }
+}
+
+pub fn block_timestamp(block: &eth::Block) -> u64 {
+    block
+        .header
+        .as_ref()
+        .and_then(|header| header.timestamp.as_ref())
+        .map(|timestamp| timestamp.seconds)
+        .unwrap_or(0) as u64
+}
+
+/// A single swap's price and traded amounts, ready to be folded into
+/// whichever bucket(s) it falls in.
+struct CandleSample {
+    pool_address: Vec<u8>,
+    price: BigDecimal,
+    amount0: BigDecimal,
+    amount1: BigDecimal,
+}
+
+/// Decimal-adjusted price from `sqrtPriceX96`, looking up the pool's token
+/// decimals from `store_pool_metadata`'s `:d0`/`:d1` keys (defaulting to 0/0,
+/// i.e. no adjustment, for a pool whose metadata hasn't been seen yet) and
+/// parsing [`price_from_sqrt_x96`]'s formatted string back into a
+/// [`BigDecimal`] for the numeric high/low/open/close sub-stores below.
+fn decimal_adjusted_price(
+    sqrt_price_x96: &BigInt,
+    pool_address: &str,
+    metadata_store: &StoreGetString,
+) -> BigDecimal {
+    let decimals0 = metadata_store
+        .get_last(format!("{pool_address}:d0"))
+        .and_then(|value| value.parse::<u32>().ok())
+        .unwrap_or(0);
+    let decimals1 = metadata_store
+        .get_last(format!("{pool_address}:d1"))
+        .and_then(|value| value.parse::<u32>().ok())
+        .unwrap_or(0);
+    let price = price_from_sqrt_x96(sqrt_price_x96, decimals0, decimals1, CANDLE_PRICE_PRECISION);
+    BigDecimal::from_str(&price).unwrap_or_default()
+}
+
+/// Iterate a block's swaps in order, deriving each one's decimal-adjusted
+/// price (V2 pools use the most recent `Sync`-derived reserve price within
+/// the same block; V3 pools use their own `sqrtPriceX96`) and hand it to
+/// `callback` once per configured window. StableSwap pools have no
+/// continuous price series here (that requires the amp/n_coins registry
+/// `map_stableswap_ticker_output` takes as `params`, which this module
+/// doesn't have), so they're skipped.
+fn for_each_candle_sample(
+    block: &eth::Block,
+    windows: &[(String, u64)],
+    metadata_store: &StoreGetString,
+    mut callback: impl FnMut(&str, u64, &CandleSample),
+) {
+    let timestamp = block_timestamp(block);
+    let adapters = AdapterRegistry::new()
+        .register(Box::new(UniswapV3Adapter))
+        .register(Box::new(PancakeswapV3Adapter));
+
+    let mut last_v2_sqrt_price: HashMap<Vec<u8>, BigInt> = HashMap::new();
+
+    for log in block.logs() {
+        if log.topics().is_empty() {
+            continue;
+        }
+
+        let sample = match log.topics()[0].as_slice() {
+            topic if topic == crate::V2_SYNC_EVENT_SIG => {
+                if log.data().len() < 64 {
+                    continue;
+                }
+                let reserve0 = uint112_to_bigint(&log.data()[0..32]);
+                let reserve1 = uint112_to_bigint(&log.data()[32..64]);
+                let sqrt_price = calculate_sqrt_price_x96(&reserve0, &reserve1);
+                last_v2_sqrt_price.insert(log.log.address.to_vec(), sqrt_price);
+                None
+            }
+            topic if topic == crate::V2_SWAP_EVENT_SIG => {
+                UniswapV2Adapter.decode_swap(&log).and_then(|swap| {
+                    last_v2_sqrt_price
+                        .get(&swap.pool_address)
+                        .map(|sqrt_price| {
+                            let pool_address =
+                                ensure_0x_prefix(&Hex(&swap.pool_address).to_string());
+                            CandleSample {
+                                pool_address: swap.pool_address.clone(),
+                                price: decimal_adjusted_price(
+                                    sqrt_price,
+                                    &pool_address,
+                                    metadata_store,
+                                ),
+                                amount0: BigDecimal::from(swap.amount0.clone()),
+                                amount1: BigDecimal::from(swap.amount1.clone()),
+                            }
+                        })
+                })
+            }
+            _ => adapters.decode(&log).and_then(|swap| {
+                swap.sqrt_price.as_ref().map(|sqrt_price| {
+                    let pool_address = ensure_0x_prefix(&Hex(&swap.pool_address).to_string());
+                    CandleSample {
+                        pool_address: swap.pool_address.clone(),
+                        price: decimal_adjusted_price(sqrt_price, &pool_address, metadata_store),
+                        amount0: BigDecimal::from(swap.amount0.clone()),
+                        amount1: BigDecimal::from(swap.amount1.clone()),
+                    }
+                })
+            }),
+        };
+
+        let Some(sample) = sample else { continue };
+        let pool_address = ensure_0x_prefix(&Hex(&sample.pool_address).to_string());
+
+        for (label, interval_seconds) in windows {
+            let bucket = timestamp / interval_seconds;
+            callback(&format!("{pool_address}:{label}:{bucket}"), bucket, &sample);
+        }
+    }
+}
+
+/// Store handler that records the first swap price of each `(pool, window,
+/// bucket)` as the candle's `open`. Uses `set_if_not_exists` so only the
+/// first write per bucket sticks.
+#[substreams::handlers::store]
+pub fn store_candle_open(
+    params: String,
+    block: eth::Block,
+    metadata_store: StoreGetString,
+    store: StoreSetIfNotExistsBigDecimal,
+) {
+    let windows = parse_windows(&params);
+    for_each_candle_sample(&block, &windows, &metadata_store, |key, _bucket, sample| {
+        store.set_if_not_exists(0, key, &sample.price);
+    });
+}
+
+/// Store handler that tracks the running high (max) price of each bucket.
+#[substreams::handlers::store]
+pub fn store_candle_high(
+    params: String,
+    block: eth::Block,
+    metadata_store: StoreGetString,
+    store: StoreMaxBigDecimal,
+) {
+    let windows = parse_windows(&params);
+    for_each_candle_sample(&block, &windows, &metadata_store, |key, _bucket, sample| {
+        store.max(0, key, sample.price.clone());
+    });
+}
+
+/// Store handler that tracks the running low (min) price of each bucket.
+#[substreams::handlers::store]
+pub fn store_candle_low(
+    params: String,
+    block: eth::Block,
+    metadata_store: StoreGetString,
+    store: StoreMinBigDecimal,
+) {
+    let windows = parse_windows(&params);
+    for_each_candle_sample(&block, &windows, &metadata_store, |key, _bucket, sample| {
+        store.min(0, key, sample.price.clone());
+    });
+}
+
+/// Store handler that records the last swap price of each bucket as the
+/// candle's `close`. Swaps are processed in block order and later blocks
+/// overwrite earlier ones, so the final write for a bucket is its true close.
+#[substreams::handlers::store]
+pub fn store_candle_close(
+    params: String,
+    block: eth::Block,
+    metadata_store: StoreGetString,
+    store: StoreSetBigDecimal,
+) {
+    let windows = parse_windows(&params);
+    for_each_candle_sample(&block, &windows, &metadata_store, |key, _bucket, sample| {
+        store.set(0, key, &sample.price);
+    });
+}
+
+/// Store handler that sums each bucket's traded token0 volume.
+#[substreams::handlers::store]
+pub fn store_candle_volume_token0(
+    params: String,
+    block: eth::Block,
+    metadata_store: StoreGetString,
+    store: StoreAddBigDecimal,
+) {
+    let windows = parse_windows(&params);
+    for_each_candle_sample(&block, &windows, &metadata_store, |key, _bucket, sample| {
+        store.add(0, key, sample.amount0.clone());
+    });
+}
+
+/// Store handler that sums each bucket's traded token1 volume.
+#[substreams::handlers::store]
+pub fn store_candle_volume_token1(
+    params: String,
+    block: eth::Block,
+    metadata_store: StoreGetString,
+    store: StoreAddBigDecimal,
+) {
+    let windows = parse_windows(&params);
+    for_each_candle_sample(&block, &windows, &metadata_store, |key, _bucket, sample| {
+        store.add(0, key, sample.amount1.clone());
+    });
+}
+
+/// Store handler that counts swaps per bucket.
+#[substreams::handlers::store]
+pub fn store_candle_swap_count(
+    params: String,
+    block: eth::Block,
+    metadata_store: StoreGetString,
+    store: StoreAddInt64,
+) {
+    let windows = parse_windows(&params);
+    for_each_candle_sample(
+        &block,
+        &windows,
+        &metadata_store,
+        |key, _bucket, _sample| {
+            store.add(0, key, 1);
+        },
+    );
+}
+
+/// Read a candle's four price sub-stores plus volume/swap-count for
+/// `{pool_address}:{window}:{bucket}`. Returns `None` if the bucket has no
+/// recorded swaps (open is unset).
+#[allow(clippy::too_many_arguments)]
+fn read_candle(
+    pool_address: &str,
+    window: &str,
+    bucket: u64,
+    interval_seconds: u64,
+    open_store: &StoreGetBigDecimal,
+    high_store: &StoreGetBigDecimal,
+    low_store: &StoreGetBigDecimal,
+    close_store: &StoreGetBigDecimal,
+    volume_token0_store: &StoreGetBigDecimal,
+    volume_token1_store: &StoreGetBigDecimal,
+    swap_count_store: &StoreGetInt64,
+    finalized: bool,
+) -> Option<DexCandle> {
+    let key = format!("{pool_address}:{window}:{bucket}");
+    let open = open_store.get_last(&key)?;
+
+    Some(DexCandle {
+        pool_address: pool_address.to_string(),
+        window: window.to_string(),
+        period_start_timestamp: bucket * interval_seconds,
+        open: format_bigdecimal(&open),
+        high: format_bigdecimal(&high_store.get_last(&key).unwrap_or_default()),
+        low: format_bigdecimal(&low_store.get_last(&key).unwrap_or_default()),
+        close: format_bigdecimal(&close_store.get_last(&key).unwrap_or_default()),
+        volume_token0: format_bigdecimal(&volume_token0_store.get_last(&key).unwrap_or_default()),
+        volume_token1: format_bigdecimal(&volume_token1_store.get_last(&key).unwrap_or_default()),
+        swap_count: swap_count_store.get_last(&key).unwrap_or_default() as u32,
+        finalized,
+    })
+}
+
+/// Map handler that emits, for every pool that trades in this block, the
+/// still-open candle for the current bucket of each window plus the previous
+/// bucket's candle (marked finalized, since its window has fully elapsed).
+/// The previous candle is re-emitted in each such block of the current
+/// bucket, not only the first. `params` selects the windows the same way the
+/// store handlers above do (default `1m,5m,1h`).
+#[allow(clippy::too_many_arguments)]
+#[substreams::handlers::map]
+pub fn map_dex_candles(
+    params: String,
+    block: eth::Block,
+    metadata_store: StoreGetString,
+    open_store: StoreGetBigDecimal,
+    high_store: StoreGetBigDecimal,
+    low_store: StoreGetBigDecimal,
+    close_store: StoreGetBigDecimal,
+    volume_token0_store: StoreGetBigDecimal,
+    volume_token1_store: StoreGetBigDecimal,
+    swap_count_store: StoreGetInt64,
+) -> Result<CandleOutput, substreams::errors::Error> {
+    let windows = parse_windows(&params);
+    let timestamp = block_timestamp(&block);
+
+    let mut traded_pools: HashSet<Vec<u8>> = HashSet::new();
+    for_each_candle_sample(
+        &block,
+        &windows,
+        &metadata_store,
+        |_key, _bucket, sample| {
+            traded_pools.insert(sample.pool_address.clone());
+        },
+    );
+
+    let mut candles = vec![];
+    for pool_address_bytes in &traded_pools {
+        let pool_address = ensure_0x_prefix(&Hex(pool_address_bytes).to_string());
+
+        for (label, interval_seconds) in &windows {
+            let bucket = timestamp / interval_seconds;
+
+            if let Some(candle) = read_candle(
+                &pool_address,
+                label,
+                bucket,
+                *interval_seconds,
+                &open_store,
+                &high_store,
+                &low_store,
+                &close_store,
+                &volume_token0_store,
+                &volume_token1_store,
+                &swap_count_store,
+                false,
+            ) {
+                candles.push(candle);
+            }
+
+            if bucket > 0 {
+                if let Some(candle) = read_candle(
+                    &pool_address,
+                    label,
+                    bucket - 1,
+                    *interval_seconds,
+                    &open_store,
+                    &high_store,
+                    &low_store,
+                    &close_store,
+                    &volume_token0_store,
+                    &volume_token1_store,
+                    &swap_count_store,
+                    true,
+                ) {
+                    candles.push(candle);
+                }
+            }
+        }
+    }
+
+    Ok(CandleOutput { candles })
+}
@@ -1,4 +1,7 @@
-use substreams::scalar::BigInt;
+use crate::adapter::NormalizedSwap;
+use dex_common::price_from_sqrt_price_x96;
+use substreams::scalar::{BigDecimal, BigInt};
+use substreams::store::{StoreAdd, StoreAddBigInt};
 
 /// Aggregation struct for pool data across all DEX versions
 #[derive(Clone)]
@@ -7,6 +10,46 @@ pub struct SwapAggregation {
     pub volume_token1: BigInt,
     pub swap_count: u32,
     pub last_sqrt_price: BigInt,
+    /// Most recent in-range active liquidity (V3 only; stays 0 for V2 pools)
+    pub last_liquidity: BigInt,
+    /// Most recent tick (V3 only; stays 0 for V2 pools)
+    pub current_tick: i32,
+    /// Running high price seen this block (token1/token0)
+    pub high_price: Option<BigDecimal>,
+    /// Running low price seen this block (token1/token0)
+    pub low_price: Option<BigDecimal>,
+    /// Sum of price * volume, for the volume-weighted average price
+    pub vwap_price_volume: BigDecimal,
+    /// Sum of volume, the VWAP denominator
+    pub vwap_volume: BigDecimal,
+    /// Cumulative PancakeSwap V3 protocol fees taken in token0 (0 for
+    /// standard Uniswap V3 pools, which don't emit this field)
+    pub protocol_fees_token0: BigInt,
+    /// Cumulative PancakeSwap V3 protocol fees taken in token1 (0 for
+    /// standard Uniswap V3 pools, which don't emit this field)
+    pub protocol_fees_token1: BigInt,
+    /// Most recent traded coin indices, for protocols where token0/token1
+    /// isn't a fixed pair (see [`NormalizedSwap::traded_indices`]). `None`
+    /// until such a swap is observed, and always `None` for V2/V3/Balancer.
+    pub last_traded_indices: Option<(u32, u32)>,
+    /// Volume from swaps where token0 flowed out of the pool (a "buy" of
+    /// token0, paid for with token1). Populated by V2/V3's own
+    /// `process_swap_event`; stays 0 for StableSwap/Balancer, which only
+    /// go through [`Self::apply_normalized_swap`].
+    pub buy_volume_token0: BigInt,
+    /// Volume from swaps where token0 flowed into the pool (a "sell" of
+    /// token0, bought with token1). See [`Self::buy_volume_token0`].
+    pub sell_volume_token0: BigInt,
+    /// Volume from swaps where token1 flowed out of the pool (a "buy" of
+    /// token1). See [`Self::buy_volume_token0`].
+    pub buy_volume_token1: BigInt,
+    /// Volume from swaps where token1 flowed into the pool (a "sell" of
+    /// token1). See [`Self::buy_volume_token0`].
+    pub sell_volume_token1: BigInt,
+    /// Count of swaps where token0 was bought (see [`Self::buy_volume_token0`]).
+    pub buy_count: u32,
+    /// Count of swaps where token0 was sold (see [`Self::sell_volume_token0`]).
+    pub sell_count: u32,
 }
 
 impl Default for SwapAggregation {
@@ -16,6 +59,114 @@ impl Default for SwapAggregation {
             volume_token1: BigInt::zero(),
             swap_count: 0,
             last_sqrt_price: BigInt::zero(),
+            last_liquidity: BigInt::zero(),
+            current_tick: 0,
+            high_price: None,
+            low_price: None,
+            vwap_price_volume: BigDecimal::zero(),
+            vwap_volume: BigDecimal::zero(),
+            protocol_fees_token0: BigInt::zero(),
+            protocol_fees_token1: BigInt::zero(),
+            last_traded_indices: None,
+            buy_volume_token0: BigInt::zero(),
+            sell_volume_token0: BigInt::zero(),
+            buy_volume_token1: BigInt::zero(),
+            sell_volume_token1: BigInt::zero(),
+            buy_count: 0,
+            sell_count: 0,
         }
     }
 }
+
+impl SwapAggregation {
+    /// Fold a new price observation into the running high/low.
+    pub fn observe_price(&mut self, price: &BigDecimal) {
+        self.high_price = Some(match &self.high_price {
+            Some(high) if high > price => high.clone(),
+            _ => price.clone(),
+        });
+        self.low_price = Some(match &self.low_price {
+            Some(low) if low < price => low.clone(),
+            _ => price.clone(),
+        });
+    }
+
+    /// Weight a traded volume by the price it traded at, for VWAP.
+    pub fn observe_vwap_sample(&mut self, price: &BigDecimal, volume: &BigDecimal) {
+        self.vwap_price_volume = self.vwap_price_volume.clone() + price.clone() * volume.clone();
+        self.vwap_volume = self.vwap_volume.clone() + volume.clone();
+    }
+
+    /// The volume-weighted average price, or zero if no volume was observed.
+    pub fn vwap(&self) -> BigDecimal {
+        if self.vwap_volume == BigDecimal::zero() {
+            return BigDecimal::zero();
+        }
+        self.vwap_price_volume.clone() / self.vwap_volume.clone()
+    }
+
+    /// Fold a protocol-agnostic [`NormalizedSwap`] into volume, swap count,
+    /// and (when a price is available) VWAP/high/low — the generic
+    /// counterpart to each DEX's own richer `process_swap_event`, which also
+    /// tracks protocol-specific fields (liquidity, tick, protocol fees) that
+    /// `NormalizedSwap` doesn't carry.
+    pub fn apply_normalized_swap(&mut self, swap: &NormalizedSwap) {
+        self.volume_token0 = self.volume_token0.clone() + swap.amount0.clone();
+        self.volume_token1 = self.volume_token1.clone() + swap.amount1.clone();
+        self.swap_count += 1;
+
+        if swap.traded_indices.is_some() {
+            self.last_traded_indices = swap.traded_indices;
+        }
+
+        if let Some(sqrt_price) = &swap.sqrt_price {
+            self.last_sqrt_price = sqrt_price.clone();
+            let price = price_from_sqrt_price_x96(sqrt_price);
+            self.observe_price(&price);
+            self.observe_vwap_sample(&price, &BigDecimal::from(swap.amount1.clone()));
+        }
+    }
+
+    /// Classify a single swap's direction and fold it into the buy/sell
+    /// splits, given each token's in/out amounts (one of each pair is zero
+    /// for a normal two-sided AMM swap). "Buy" and "sell" are both stated
+    /// from token0's perspective: token0 flowing out of the pool is a buy
+    /// (paid for with token1 flowing in), and vice versa for a sell.
+    pub fn observe_swap_direction(
+        &mut self,
+        amount0_in: &BigInt,
+        amount1_in: &BigInt,
+        amount0_out: &BigInt,
+        amount1_out: &BigInt,
+    ) {
+        if *amount0_in > BigInt::zero() {
+            self.sell_volume_token0 = self.sell_volume_token0.clone() + amount0_in.clone();
+            self.buy_volume_token1 = self.buy_volume_token1.clone() + amount1_out.clone();
+            self.sell_count += 1;
+        } else if *amount1_in > BigInt::zero() {
+            self.sell_volume_token1 = self.sell_volume_token1.clone() + amount1_in.clone();
+            self.buy_volume_token0 = self.buy_volume_token0.clone() + amount0_out.clone();
+            self.buy_count += 1;
+        }
+    }
+}
+
+/// Accumulate a signed per-coin reserve delta under a shared
+/// `{pool_key}:coin{coin_index}` keyspace, reorg-safely via the log's
+/// ordinal. Shared by StableSwap's and Balancer's own reserve-tracking
+/// stores (`stableswap::store_stableswap_reserves` /
+/// `balancer::store_balancer_reserves`): both reconstruct a pool's live
+/// per-coin balance as a net flow from swap deltas, the same "accumulate
+/// from zero" approach `pool_stats::store_pool_lifetime_reserves` already
+/// uses for V3 (neither protocol emits a `Sync`-style absolute-reserve
+/// event), so the actual accumulation is written once here instead of
+/// twice.
+pub fn apply_coin_reserve_delta(
+    store: &StoreAddBigInt,
+    ordinal: u64,
+    pool_key: &str,
+    coin_index: u32,
+    delta: BigInt,
+) {
+    store.add(ordinal, format!("{pool_key}:coin{coin_index}"), delta);
+}
@@ -16,18 +16,47 @@
 //    Clients must calculate actual price using:
 //    price = (sqrtPriceX96 / 2^96)^2 * 10^(token0_decimals - token1_decimals)
 
+mod adapter;
+mod balancer;
+mod candles;
 mod common;
 mod pb;
+mod pool_stats;
+mod reserves;
+mod stableswap;
+mod token_prices;
 mod v2;
 mod v3;
 
+use crate::adapter::AdapterRegistry;
 use crate::common::SwapAggregation;
 use crate::pb::dex::common::v1::{PoolTicker, TickerOutput};
-use dex_common::{ensure_0x_prefix, format_bigint};
+use crate::stableswap::{StableSwapAggregation, StableSwapPoolConfig};
+use crate::token_prices::ETH_USD_KEY;
+use dex_common::{
+    ensure_0x_prefix, format_bigdecimal, format_bigint, format_bigint_encoded, NumericEncoding,
+    price_from_sqrt_price_x96,
+};
 use std::collections::HashMap;
+use std::str::FromStr;
+use substreams::scalar::BigDecimal;
+use substreams::store::{StoreGet, StoreGetBigDecimal, StoreGetBigInt};
 use substreams::Hex;
 use substreams_ethereum::pb::eth::v2 as eth;
 
+pub use balancer::store_balancer_reserves;
+pub use candles::{
+    map_dex_candles, store_candle_close, store_candle_high, store_candle_low, store_candle_open,
+    store_candle_swap_count, store_candle_volume_token0, store_candle_volume_token1,
+};
+pub use pool_stats::{
+    map_pool_lifetime_stats, store_pool_lifetime_reserves, store_pool_metadata,
+    store_pool_swap_count, store_pool_volume,
+};
+pub use reserves::{store_active_liquidity, store_reserves};
+pub use stableswap::store_stableswap_reserves;
+pub use token_prices::store_token_prices;
+
 // Event signatures (keccak256 hashes)
 
 // V2 events (Uniswap V2, SushiSwap, PancakeSwap V2, QuickSwap V2, etc.)
@@ -42,10 +71,36 @@ const UNISWAP_V3_SWAP_EVENT_SIG: [u8; 32] =
 const PANCAKESWAP_V3_SWAP_EVENT_SIG: [u8; 32] =
     hex_literal::hex!("19b47279256b2a23a1665c810c8d55a1758940ee09377d4f8d26497a3577dc83");
 
+/// `params` optionally selects the numeric output encoding via `encoding=hex`
+/// (default `encoding=decimal`), applied to `block_volume_token0/1`,
+/// `sqrt_price_x96` and the buy/sell volume fields. It also carries the same token-price registry
+/// `store_token_prices` reads (see [`token_prices::parse_params`]):
+/// `weth=<address>`, `base=<address>` per stablecoin, and
+/// `pool:token0:token1:decimals0:decimals1` per pool, used here to resolve
+/// `volume_token0_usd`/`volume_token1_usd`. A pool missing from that registry
+/// (or a token with no resolvable price) gets empty USD fields.
 #[substreams::handlers::map]
-pub fn map_dex_ticker_output(block: eth::Block) -> Result<TickerOutput, substreams::errors::Error> {
+pub fn map_dex_ticker_output(
+    params: String,
+    block: eth::Block,
+    reserves_store: StoreGetBigInt,
+    token_prices_store: StoreGetBigDecimal,
+) -> Result<TickerOutput, substreams::errors::Error> {
+    let encoding = NumericEncoding::from_params(&params);
+    let (_, _, pool_token_registry) = token_prices::parse_params(&params);
     let mut pool_aggregations: HashMap<Vec<u8>, SwapAggregation> = HashMap::new();
 
+    // Only StableSwap and Balancer are routed through the registry here: V2/V3
+    // go through their own `process_*` functions below instead, since those
+    // track protocol-specific fields (Sync-derived reserve price, V3
+    // liquidity/tick/protocol fees, buy/sell classification) that
+    // `NormalizedSwap` deliberately doesn't carry. Registering `UniswapV2Adapter`
+    // / `UniswapV3Adapter` / `PancakeswapV3Adapter` here too would be dead code,
+    // since this match never reaches `adapters.decode` for their topics.
+    let adapters = AdapterRegistry::new()
+        .register(Box::new(stableswap::StableSwapAdapter))
+        .register(Box::new(balancer::BalancerAdapter));
+
     // Process all DEX events
     for log in block.logs() {
         // Early exit if no topics
@@ -70,6 +125,21 @@ pub fn map_dex_ticker_output(block: eth::Block) -> Result<TickerOutput, substrea
                 v3::process_swap_event(&log, &mut pool_aggregations)
             }
 
+            // StableSwap and Balancer, via the generic NormalizedSwap path: no
+            // protocol-specific fields to track, so the adapter registry's
+            // decode is all aggregation needs here.
+            topic
+                if topic == stableswap::TOKEN_EXCHANGE_EVENT_SIG
+                    || topic == balancer::BALANCER_SWAP_EVENT_SIG =>
+            {
+                if let Some(swap) = adapters.decode(&log) {
+                    pool_aggregations
+                        .entry(swap.pool_address.clone())
+                        .or_default()
+                        .apply_normalized_swap(&swap);
+                }
+            }
+
             _ => {}
         }
     }
@@ -92,12 +162,272 @@ pub fn map_dex_ticker_output(block: eth::Block) -> Result<TickerOutput, substrea
     for (pool_address_bytes, aggregation) in pool_aggregations {
         let pool_address = ensure_0x_prefix(&Hex(&pool_address_bytes).to_string());
 
+        // V2 pools persist their Sync-derived reserves under `:r0`/`:r1`; V3
+        // pools have no token-unit reserves, so these stay at their zero
+        // default (active liquidity is already reported separately via
+        // `aggregation.last_liquidity` below).
+        let reserve0 = reserves_store
+            .get_last(format!("{pool_address}:r0"))
+            .unwrap_or_default();
+        let reserve1 = reserves_store
+            .get_last(format!("{pool_address}:r1"))
+            .unwrap_or_default();
+
+        // TVL in token1 terms: reserve1 + reserve0 * price, where price comes
+        // from the same sqrtPriceX96 the ticker already reports.
+        let price = price_from_sqrt_price_x96(&aggregation.last_sqrt_price);
+        let tvl_token1 = BigDecimal::from(reserve1.clone()) + BigDecimal::from(reserve0.clone()) * price;
+
+        // USD volume, via `store_token_prices`'s on-chain-derived `derivedEth`
+        // per token and the ETH/USD reference rate. Empty if the pool isn't in
+        // the token-price registry or either side has no resolvable price.
+        let (volume_token0_usd, volume_token1_usd) = pool_token_registry
+            .get(&pool_address_bytes)
+            .and_then(|config| {
+                let eth_usd = token_prices_store.get_last(ETH_USD_KEY)?;
+                let token0_address = ensure_0x_prefix(&Hex(&config.token0).to_string());
+                let token1_address = ensure_0x_prefix(&Hex(&config.token1).to_string());
+                let derived_eth0 = token_prices_store.get_last(&token0_address)?;
+                let derived_eth1 = token_prices_store.get_last(&token1_address)?;
+
+                let scale0 = BigDecimal::from_str(&format!("1e{}", config.decimals0)).ok()?;
+                let scale1 = BigDecimal::from_str(&format!("1e{}", config.decimals1)).ok()?;
+
+                let usd0 = BigDecimal::from(aggregation.volume_token0.clone()) / scale0
+                    * derived_eth0
+                    * eth_usd.clone();
+                let usd1 = BigDecimal::from(aggregation.volume_token1.clone()) / scale1
+                    * derived_eth1
+                    * eth_usd;
+
+                Some((format_bigdecimal(&usd0), format_bigdecimal(&usd1)))
+            })
+            .unwrap_or_default();
+
         tickers.push(PoolTicker {
             pool_address,
-            block_volume_token0: format_bigint(&aggregation.volume_token0),
-            block_volume_token1: format_bigint(&aggregation.volume_token1),
+            block_volume_token0: format_bigint_encoded(&aggregation.volume_token0, encoding),
+            block_volume_token1: format_bigint_encoded(&aggregation.volume_token1, encoding),
+            swap_count: aggregation.swap_count,
+            sqrt_price_x96: format_bigint_encoded(&aggregation.last_sqrt_price, encoding),
+            close_price: format_bigdecimal(&price),
+            liquidity: format_bigint(&aggregation.last_liquidity),
+            current_tick: aggregation.current_tick,
+            vwap: format_bigdecimal(&aggregation.vwap()),
+            high_price: format_bigdecimal(&aggregation.high_price.clone().unwrap_or_default()),
+            low_price: format_bigdecimal(&aggregation.low_price.clone().unwrap_or_default()),
+            protocol_fees_token0: format_bigint(&aggregation.protocol_fees_token0),
+            protocol_fees_token1: format_bigint(&aggregation.protocol_fees_token1),
+            reserve0: format_bigint(&reserve0),
+            reserve1: format_bigint(&reserve1),
+            tvl_token1: format_bigdecimal(&tvl_token1),
+            volume_token0_usd,
+            volume_token1_usd,
+            // The coins actually traded, for pools where token0/token1 isn't
+            // a fixed pair (StableSwap, routed through here too). Defaults to
+            // the ordinary 0/1 pair for every other protocol.
+            traded_coin0_index: aggregation.last_traded_indices.map(|(i, _)| i).unwrap_or(0),
+            traded_coin1_index: aggregation.last_traded_indices.map(|(_, j)| j).unwrap_or(1),
+            // Directional split of the volumes above, for order-flow
+            // imbalance. Only V2/V3's own `process_swap_event` populate
+            // these (see `SwapAggregation::observe_swap_direction`); they
+            // stay at their zero default for StableSwap/Balancer swaps
+            // routed through here.
+            buy_volume_token0: format_bigint_encoded(&aggregation.buy_volume_token0, encoding),
+            sell_volume_token0: format_bigint_encoded(&aggregation.sell_volume_token0, encoding),
+            buy_volume_token1: format_bigint_encoded(&aggregation.buy_volume_token1, encoding),
+            sell_volume_token1: format_bigint_encoded(&aggregation.sell_volume_token1, encoding),
+            buy_count: aggregation.buy_count,
+            sell_count: aggregation.sell_count,
+            block_number: block.number,
+            timestamp: timestamp_seconds,
+        });
+    }
+
+    Ok(TickerOutput { tickers })
+}
+
+/// Map handler for Curve/Saddle-style StableSwap pools (and their LSD
+/// variants), which don't fit the constant-product/concentrated-liquidity
+/// model V2/V3 use. `params` configures each pool's amplification coefficient
+/// and coin count as `pool:amp:n_coins,pool:amp:n_coins,...`, since neither is
+/// carried on the `TokenExchange` event.
+#[substreams::handlers::map]
+pub fn map_stableswap_ticker_output(
+    params: String,
+    block: eth::Block,
+    reserves_store: StoreGetBigInt,
+) -> Result<TickerOutput, substreams::errors::Error> {
+    let encoding = NumericEncoding::from_params(&params);
+    let registry: HashMap<Vec<u8>, StableSwapPoolConfig> = stableswap::parse_registry(&params);
+    let mut pool_aggregations: HashMap<Vec<u8>, StableSwapAggregation> = HashMap::new();
+
+    for log in block.logs() {
+        if log.topics().is_empty() {
+            continue;
+        }
+
+        if log.topics()[0] == stableswap::TOKEN_EXCHANGE_EVENT_SIG {
+            stableswap::process_stableswap_event(
+                &log,
+                &registry,
+                &reserves_store,
+                &mut pool_aggregations,
+            );
+        }
+    }
+
+    let timestamp_seconds = block
+        .header
+        .as_ref()
+        .and_then(|header| header.timestamp.as_ref())
+        .map(|timestamp| timestamp.seconds as u64)
+        .ok_or_else(|| {
+            substreams::errors::Error::msg(format!(
+                "Block {} missing header or timestamp",
+                block.number
+            ))
+        })?;
+
+    let mut tickers = vec![];
+
+    for (pool_address_bytes, aggregation) in pool_aggregations {
+        tickers.push(PoolTicker {
+            pool_address: stableswap::format_pool_address(&pool_address_bytes),
+            block_volume_token0: format_bigint_encoded(&aggregation.base.volume_token0, encoding),
+            block_volume_token1: format_bigint_encoded(&aggregation.base.volume_token1, encoding),
+            swap_count: aggregation.base.swap_count,
+            sqrt_price_x96: String::new(),
+            close_price: stableswap::format_close_price(&aggregation),
+            liquidity: String::new(),
+            current_tick: 0,
+            // StableSwap pools don't expose a continuous sqrt-price series to
+            // derive a VWAP/high/low from, so these stay at their zero default.
+            vwap: String::new(),
+            high_price: String::new(),
+            low_price: String::new(),
+            protocol_fees_token0: String::new(),
+            protocol_fees_token1: String::new(),
+            // StableSwap pools don't emit per-token reserves the way V2 does
+            // (balances live across N coins, not a token0/token1 pair), so TVL
+            // isn't derivable here without a broader invariant-based model.
+            reserve0: String::new(),
+            reserve1: String::new(),
+            tvl_token1: String::new(),
+            // This mapper has no `store_token_prices` input (StableSwap pools
+            // aren't in that registry), so USD volume has no path here either.
+            volume_token0_usd: String::new(),
+            volume_token1_usd: String::new(),
+            traded_coin0_index: aggregation
+                .base
+                .last_traded_indices
+                .map(|(i, _)| i)
+                .unwrap_or(0),
+            traded_coin1_index: aggregation
+                .base
+                .last_traded_indices
+                .map(|(_, j)| j)
+                .unwrap_or(1),
+            // StableSwap swaps don't go through `process_swap_event`'s
+            // directional classification (see `map_dex_ticker_output`), so
+            // these stay at their zero default here too.
+            buy_volume_token0: String::new(),
+            sell_volume_token0: String::new(),
+            buy_volume_token1: String::new(),
+            sell_volume_token1: String::new(),
+            buy_count: 0,
+            sell_count: 0,
+            block_number: block.number,
+            timestamp: timestamp_seconds,
+        });
+    }
+
+    Ok(TickerOutput { tickers })
+}
+
+/// Map handler for Balancer V2 weighted pools, which trade through a single
+/// shared Vault contract rather than per-pool contracts. `params` configures
+/// each pool's token0/token1 assignment and weights as
+/// `poolid:token0:token1:weight0_bps:weight1_bps,poolid:...`, since none of
+/// that is carried on the Vault's `Swap` event (see
+/// [`balancer::parse_registry`]).
+#[substreams::handlers::map]
+pub fn map_balancer_ticker_output(
+    params: String,
+    block: eth::Block,
+    reserves_store: StoreGetBigInt,
+) -> Result<TickerOutput, substreams::errors::Error> {
+    let encoding = NumericEncoding::from_params(&params);
+    let registry = balancer::parse_registry(&params);
+    let mut pool_aggregations: HashMap<Vec<u8>, SwapAggregation> = HashMap::new();
+
+    for log in block.logs() {
+        if log.topics().is_empty() {
+            continue;
+        }
+
+        if log.topics()[0] == balancer::BALANCER_SWAP_EVENT_SIG {
+            balancer::process_balancer_event(
+                &log,
+                &registry,
+                &reserves_store,
+                &mut pool_aggregations,
+            );
+        }
+    }
+
+    let timestamp_seconds = block
+        .header
+        .as_ref()
+        .and_then(|header| header.timestamp.as_ref())
+        .map(|timestamp| timestamp.seconds as u64)
+        .ok_or_else(|| {
+            substreams::errors::Error::msg(format!(
+                "Block {} missing header or timestamp",
+                block.number
+            ))
+        })?;
+
+    let mut tickers = vec![];
+
+    for (pool_id_bytes, aggregation) in pool_aggregations {
+        let close_price = price_from_sqrt_price_x96(&aggregation.last_sqrt_price);
+
+        tickers.push(PoolTicker {
+            pool_address: balancer::format_pool_id(&pool_id_bytes),
+            block_volume_token0: format_bigint_encoded(&aggregation.volume_token0, encoding),
+            block_volume_token1: format_bigint_encoded(&aggregation.volume_token1, encoding),
             swap_count: aggregation.swap_count,
-            sqrt_price_x96: format_bigint(&aggregation.last_sqrt_price),
+            sqrt_price_x96: format_bigint_encoded(&aggregation.last_sqrt_price, encoding),
+            close_price: format_bigdecimal(&close_price),
+            liquidity: String::new(),
+            current_tick: 0,
+            vwap: format_bigdecimal(&aggregation.vwap()),
+            high_price: format_bigdecimal(&aggregation.high_price.clone().unwrap_or_default()),
+            low_price: format_bigdecimal(&aggregation.low_price.clone().unwrap_or_default()),
+            protocol_fees_token0: String::new(),
+            protocol_fees_token1: String::new(),
+            // Weighted-pool balances live in the Vault, keyed by poolId, not
+            // as a reserve pair on the pool itself — not derivable here.
+            reserve0: String::new(),
+            reserve1: String::new(),
+            tvl_token1: String::new(),
+            // This mapper has no `store_token_prices` input (Balancer pools
+            // aren't in that registry), so USD volume has no path here either.
+            volume_token0_usd: String::new(),
+            volume_token1_usd: String::new(),
+            // Balancer pools here are always a single token0/token1 pair.
+            traded_coin0_index: 0,
+            traded_coin1_index: 1,
+            // Balancer swaps don't go through `process_swap_event`'s
+            // directional classification (see `map_dex_ticker_output`), so
+            // these stay at their zero default here too.
+            buy_volume_token0: String::new(),
+            sell_volume_token0: String::new(),
+            buy_volume_token1: String::new(),
+            sell_volume_token1: String::new(),
+            buy_count: 0,
+            sell_count: 0,
             block_number: block.number,
             timestamp: timestamp_seconds,
         });
@@ -0,0 +1,259 @@
+// ─────────────────────────────────────────────────────────────────────────────
+// Cumulative, reorg-safe per-pool lifetime statistics
+// ─────────────────────────────────────────────────────────────────────────────
+
+use crate::pb::dex::common::v1::{PoolStat, PoolStats};
+use crate::token_prices;
+use dex_common::{ensure_0x_prefix, format_bigdecimal, format_bigint};
+use std::collections::HashSet;
+use std::str::FromStr;
+use substreams::scalar::{BigDecimal, BigInt};
+use substreams::store::{
+    StoreAdd, StoreAddBigInt, StoreAddInt64, StoreGet, StoreGetBigDecimal, StoreGetBigInt,
+    StoreGetInt64, StoreGetString, StoreNew, StoreSetIfNotExists, StoreSetIfNotExistsString,
+};
+use substreams::Hex;
+use substreams_ethereum::pb::eth::v2 as eth;
+
+/// Store handler that records each pool's token addresses and decimals (from
+/// the same registry `store_token_prices` reads, see
+/// [`token_prices::parse_params`]) the first time that pool is observed.
+/// Unlike `params` itself, this persists across blocks under the engine's
+/// reorg-safe store semantics, so later blocks don't need `params` to still
+/// carry a pool once it's been seen.
+#[substreams::handlers::store]
+pub fn store_pool_metadata(params: String, block: eth::Block, store: StoreSetIfNotExistsString) {
+    let (_, _, registry) = token_prices::parse_params(&params);
+
+    let mut seen_pools: HashSet<Vec<u8>> = HashSet::new();
+    for log in block.logs() {
+        if log.topics().is_empty() {
+            continue;
+        }
+        seen_pools.insert(log.log.address.to_vec());
+    }
+
+    for pool in seen_pools {
+        let Some(config) = registry.get(&pool) else {
+            continue;
+        };
+        let pool_address = ensure_0x_prefix(&Hex(&pool).to_string());
+        store.set_if_not_exists(
+            0,
+            format!("{pool_address}:token0"),
+            ensure_0x_prefix(&Hex(&config.token0).to_string()),
+        );
+        store.set_if_not_exists(
+            0,
+            format!("{pool_address}:token1"),
+            ensure_0x_prefix(&Hex(&config.token1).to_string()),
+        );
+        store.set_if_not_exists(
+            0,
+            format!("{pool_address}:d0"),
+            config.decimals0.to_string(),
+        );
+        store.set_if_not_exists(
+            0,
+            format!("{pool_address}:d1"),
+            config.decimals1.to_string(),
+        );
+    }
+}
+
+/// Store handler that accumulates each pool's lifetime swap count. Uses the
+/// log's ordinal (its position within the block) rather than a fixed `0`, so
+/// the substreams engine can correctly unwind the delta this log contributed
+/// if the block it came from is later reorged out.
+#[substreams::handlers::store]
+pub fn store_pool_swap_count(block: eth::Block, store: StoreAddInt64) {
+    for log in block.logs() {
+        if log.topics().is_empty() {
+            continue;
+        }
+        if log.topics()[0] == crate::V2_SWAP_EVENT_SIG
+            || log.topics()[0] == crate::UNISWAP_V3_SWAP_EVENT_SIG
+            || log.topics()[0] == crate::PANCAKESWAP_V3_SWAP_EVENT_SIG
+        {
+            let pool_address = ensure_0x_prefix(&Hex(&log.log.address).to_string());
+            store.add(log.ordinal(), format!("{pool_address}:swaps"), 1);
+        }
+    }
+}
+
+/// Store handler that accumulates each pool's lifetime traded volume
+/// (token0/token1, raw units), keyed by ordinal for the same reorg-safety
+/// reason as [`store_pool_swap_count`].
+#[substreams::handlers::store]
+pub fn store_pool_volume(block: eth::Block, store: StoreAddBigInt) {
+    for log in block.logs() {
+        if log.topics().is_empty() {
+            continue;
+        }
+
+        let (volume0, volume1) = if log.topics()[0] == crate::V2_SWAP_EVENT_SIG {
+            if log.data().len() < 128 {
+                continue;
+            }
+            let amount0_in = dex_common::uint256_to_bigint(&log.data()[0..32]);
+            let amount1_in = dex_common::uint256_to_bigint(&log.data()[32..64]);
+            let amount0_out = dex_common::uint256_to_bigint(&log.data()[64..96]);
+            let amount1_out = dex_common::uint256_to_bigint(&log.data()[96..128]);
+            (amount0_in + amount0_out, amount1_in + amount1_out)
+        } else if log.topics()[0] == crate::UNISWAP_V3_SWAP_EVENT_SIG
+            || log.topics()[0] == crate::PANCAKESWAP_V3_SWAP_EVENT_SIG
+        {
+            if log.data().len() < 64 {
+                continue;
+            }
+            let amount0 = dex_common::int256_to_bigint(&log.data()[0..32]);
+            let amount1 = dex_common::int256_to_bigint(&log.data()[32..64]);
+            (abs(amount0), abs(amount1))
+        } else {
+            continue;
+        };
+
+        let pool_address = ensure_0x_prefix(&Hex(&log.log.address).to_string());
+        store.add(log.ordinal(), format!("{pool_address}:v0"), volume0);
+        store.add(log.ordinal(), format!("{pool_address}:v1"), volume1);
+    }
+}
+
+fn abs(value: BigInt) -> BigInt {
+    if value < BigInt::zero() {
+        BigInt::zero() - value
+    } else {
+        value
+    }
+}
+
+/// Store handler that reconstructs a running reserve pair per V3 pool,
+/// reorg-safely, as a net flow accumulated from signed swap deltas (amount0/1
+/// added on the way in, subtracted on the way out), seeded at zero rather
+/// than the pool's true token balance at creation — a directional proxy for
+/// reserve changes, not an absolute reserve figure. V2 pools don't need this:
+/// their `Sync` events already carry an authoritative absolute reserve
+/// snapshot, which `store_reserves` (in `reserves.rs`) persists directly via
+/// `StoreSetBigInt`; accumulating it here too would double-count it.
+#[substreams::handlers::store]
+pub fn store_pool_lifetime_reserves(block: eth::Block, store: StoreAddBigInt) {
+    for log in block.logs() {
+        if log.topics().is_empty() {
+            continue;
+        }
+        let pool_address = ensure_0x_prefix(&Hex(&log.log.address).to_string());
+
+        if log.topics()[0] == crate::UNISWAP_V3_SWAP_EVENT_SIG
+            || log.topics()[0] == crate::PANCAKESWAP_V3_SWAP_EVENT_SIG
+        {
+            if log.data().len() < 64 {
+                continue;
+            }
+            let amount0 = dex_common::int256_to_bigint(&log.data()[0..32]);
+            let amount1 = dex_common::int256_to_bigint(&log.data()[32..64]);
+            store.add(log.ordinal(), format!("{pool_address}:reserve0"), amount0);
+            store.add(log.ordinal(), format!("{pool_address}:reserve1"), amount1);
+        }
+    }
+}
+
+/// Map handler that surfaces the running totals the stores above maintain as
+/// a `PoolStats` output, for every pool touched in this block. TVL is
+/// `reserve1 + reserve0 * derivedEth[token0] / derivedEth[token1]`, with each
+/// reserve first divided by `10^decimals` of its token, when both
+/// sides have a resolvable reference price (see
+/// [`crate::token_prices::store_token_prices`]), otherwise empty — the same
+/// "no path to a base asset" fallback `volume_token0_usd`/`volume_token1_usd`
+/// already use.
+#[substreams::handlers::map]
+pub fn map_pool_lifetime_stats(
+    block: eth::Block,
+    swap_count_store: StoreGetInt64,
+    volume_store: StoreGetBigInt,
+    v3_reserve_deltas_store: StoreGetBigInt,
+    v2_reserves_store: StoreGetBigInt,
+    metadata_store: StoreGetString,
+    token_prices_store: StoreGetBigDecimal,
+) -> Result<PoolStats, substreams::errors::Error> {
+    let timestamp_seconds = block
+        .header
+        .as_ref()
+        .and_then(|header| header.timestamp.as_ref())
+        .map(|timestamp| timestamp.seconds as u64)
+        .unwrap_or(0);
+
+    let mut touched_pools: HashSet<Vec<u8>> = HashSet::new();
+    for log in block.logs() {
+        if !log.topics().is_empty() {
+            touched_pools.insert(log.log.address.to_vec());
+        }
+    }
+
+    let mut pools = vec![];
+    for pool_bytes in touched_pools {
+        let pool_address = ensure_0x_prefix(&Hex(&pool_bytes).to_string());
+
+        let cumulative_swap_count = swap_count_store
+            .get_last(format!("{pool_address}:swaps"))
+            .unwrap_or(0);
+        let cumulative_volume_token0 = volume_store
+            .get_last(format!("{pool_address}:v0"))
+            .unwrap_or_default();
+        let cumulative_volume_token1 = volume_store
+            .get_last(format!("{pool_address}:v1"))
+            .unwrap_or_default();
+        // V2 pools carry an authoritative absolute reserve snapshot (see
+        // `store_reserves` in `reserves.rs`); V3 pools have none, so fall
+        // back to the net-flow proxy `store_pool_lifetime_reserves` tracks.
+        let reserve0 = v2_reserves_store
+            .get_last(format!("{pool_address}:r0"))
+            .or_else(|| v3_reserve_deltas_store.get_last(format!("{pool_address}:reserve0")))
+            .unwrap_or_default();
+        let reserve1 = v2_reserves_store
+            .get_last(format!("{pool_address}:r1"))
+            .or_else(|| v3_reserve_deltas_store.get_last(format!("{pool_address}:reserve1")))
+            .unwrap_or_default();
+
+        let tvl = metadata_store
+            .get_last(format!("{pool_address}:token0"))
+            .zip(metadata_store.get_last(format!("{pool_address}:token1")))
+            .and_then(|(token0, token1)| {
+                let derived_eth0 = token_prices_store.get_last(&token0)?;
+                let derived_eth1 = token_prices_store.get_last(&token1)?;
+                if derived_eth1 == BigDecimal::zero() {
+                    return None;
+                }
+                let d0 = metadata_store
+                    .get_last(format!("{pool_address}:d0"))
+                    .and_then(|value| value.parse::<u32>().ok())
+                    .unwrap_or(0);
+                let d1 = metadata_store
+                    .get_last(format!("{pool_address}:d1"))
+                    .and_then(|value| value.parse::<u32>().ok())
+                    .unwrap_or(0);
+                let scale0 = BigDecimal::from_str(&format!("1e{d0}")).ok()?;
+                let scale1 = BigDecimal::from_str(&format!("1e{d1}")).ok()?;
+
+                let token0_in_token1 = derived_eth0 / derived_eth1;
+                let reserve0_adjusted = BigDecimal::from(reserve0.clone()) / scale0;
+                let reserve1_adjusted = BigDecimal::from(reserve1.clone()) / scale1;
+                Some(reserve1_adjusted + reserve0_adjusted * token0_in_token1)
+            });
+
+        pools.push(PoolStat {
+            pool_address,
+            cumulative_volume_token0: format_bigint(&cumulative_volume_token0),
+            cumulative_volume_token1: format_bigint(&cumulative_volume_token1),
+            cumulative_swap_count,
+            reserve0: format_bigint(&reserve0),
+            reserve1: format_bigint(&reserve1),
+            tvl: tvl
+                .map(|value| format_bigdecimal(&value))
+                .unwrap_or_default(),
+            block_number: block.number,
+            timestamp: timestamp_seconds,
+        });
+    }
+
+    Ok(PoolStats { pools })
+}
@@ -0,0 +1,81 @@
+// ─────────────────────────────────────────────────────────────────────────────
+// Pool reserves and active-liquidity stores
+// ─────────────────────────────────────────────────────────────────────────────
+
+use dex_common::{ensure_0x_prefix, uint112_to_bigint, uint128_to_bigint};
+use substreams::scalar::BigInt;
+use substreams::store::{StoreAdd, StoreAddBigInt, StoreNew, StoreSet, StoreSetBigInt};
+use substreams::Hex;
+use substreams_ethereum::pb::eth::v2 as eth;
+
+// Mint(address,address,int24,int24,uint128,uint256,uint256)
+const V3_MINT_EVENT_SIG: [u8; 32] =
+    hex_literal::hex!("7a53080ba414158be7ec69b987b5fb7d07dee101fe85488f0853ae16239d0bde");
+// Burn(address,int24,int24,uint128,uint256,uint256)
+const V3_BURN_EVENT_SIG: [u8; 32] =
+    hex_literal::hex!("0c396cd989a39f4459b5fa1aed6a9a8dcdbc45908acfd67e028cd568da98982c");
+
+/// Store handler that persists each V2 pool's latest known reserves from
+/// `Sync` events, so `map_dex_ticker_output`, `store_token_prices` and
+/// `map_pool_lifetime_stats` can read them across blocks. `map_dex_ticker_output`
+/// still only emits tickers for pools with events in the current block.
+/// `process_sync_event` already decodes these
+/// same reserves to derive `sqrtPriceX96`; this keeps them instead of
+/// discarding them after that.
+#[substreams::handlers::store]
+pub fn store_reserves(block: eth::Block, store: StoreSetBigInt) {
+    for log in block.logs() {
+        if log.topics().is_empty() {
+            continue;
+        }
+
+        if log.topics()[0] == crate::V2_SYNC_EVENT_SIG {
+            if log.data().len() < 64 {
+                continue;
+            }
+
+            let reserve0 = uint112_to_bigint(&log.data()[0..32]);
+            let reserve1 = uint112_to_bigint(&log.data()[32..64]);
+            let pool_address = ensure_0x_prefix(&Hex(&log.log.address).to_string());
+
+            store.set(0, format!("{pool_address}:r0"), &reserve0);
+            store.set(0, format!("{pool_address}:r1"), &reserve1);
+        }
+    }
+}
+
+/// Store handler that maintains each V3 pool's net active liquidity by
+/// accumulating `Mint`/`Burn` amount deltas (`+amount` / `-amount`). V3 has no
+/// token-unit reserves the way V2 does — this persists the same `liquidity`
+/// unit the `Swap` event already carries per-block, across blocks that have
+/// no swap at all.
+#[substreams::handlers::store]
+pub fn store_active_liquidity(block: eth::Block, store: StoreAddBigInt) {
+    for log in block.logs() {
+        if log.topics().is_empty() {
+            continue;
+        }
+
+        let pool_address = ensure_0x_prefix(&Hex(&log.log.address).to_string());
+
+        if log.topics()[0] == V3_MINT_EVENT_SIG {
+            // Mint(address sender, address indexed owner, int24 indexed tickLower,
+            // int24 indexed tickUpper, uint128 amount, uint256 amount0, uint256 amount1)
+            // Non-indexed data: sender(32) + amount(32) + amount0(32) + amount1(32)
+            if log.data().len() < 64 {
+                continue;
+            }
+            let amount = uint128_to_bigint(&log.data()[32..64]);
+            store.add(0, format!("{pool_address}:liquidity"), amount);
+        } else if log.topics()[0] == V3_BURN_EVENT_SIG {
+            // Burn(address indexed owner, int24 indexed tickLower, int24 indexed tickUpper,
+            // uint128 amount, uint256 amount0, uint256 amount1)
+            // Non-indexed data: amount(32) + amount0(32) + amount1(32)
+            if log.data().len() < 32 {
+                continue;
+            }
+            let amount = uint128_to_bigint(&log.data()[0..32]);
+            store.add(0, format!("{pool_address}:liquidity"), BigInt::zero() - amount);
+        }
+    }
+}
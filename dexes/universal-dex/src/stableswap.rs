@@ -0,0 +1,373 @@
+use crate::adapter::{DexAdapter, NormalizedSwap};
+use crate::common::{apply_coin_reserve_delta, SwapAggregation};
+use dex_common::{ensure_0x_prefix, format_bigdecimal, uint256_to_bigint};
+use std::collections::HashMap;
+use std::str::FromStr;
+use substreams::scalar::{BigDecimal, BigInt};
+use substreams::store::{StoreAddBigInt, StoreGet, StoreGetBigInt, StoreNew};
+use substreams_ethereum::block_view::LogView;
+use substreams_ethereum::pb::eth::v2 as eth;
+
+// TokenExchange(address indexed buyer, int128 sold_id, uint256 tokens_sold, int128 bought_id, uint256 tokens_bought)
+pub const TOKEN_EXCHANGE_EVENT_SIG: [u8; 32] =
+    hex_literal::hex!("8b3e96f2b889fa771c53c981b40daf005f63f637f1869f707052d15a3dd97140");
+
+// Small epsilon used for the finite-difference spot price, expressed in the
+// same raw token units as the balances read from `store_stableswap_reserves`.
+const PRICE_EPSILON: &str = "0.000000001";
+
+fn decimal(n: u64) -> BigDecimal {
+    BigDecimal::from_str(&n.to_string()).unwrap()
+}
+
+/// Per-pool configuration that can't be recovered from a `TokenExchange` log:
+/// the amplification coefficient and the number of coins in the pool.
+#[derive(Clone, Copy)]
+pub struct StableSwapPoolConfig {
+    pub amplification: u64,
+    pub n_coins: u32,
+}
+
+/// Parse a `0x`-prefixed hex address into raw bytes without pulling in a hex crate.
+pub(crate) fn parse_hex_address(address: &str) -> Option<Vec<u8>> {
+    let hex_str = address.trim_start_matches("0x").trim_start_matches("0X");
+    if hex_str.len() % 2 != 0 {
+        return None;
+    }
+
+    (0..hex_str.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex_str[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Registry of StableSwap pool configs, built from the `params` string passed
+/// to `map_stableswap_ticker_output` (format: `pool:amp:n_coins,pool:amp:n_coins,...`).
+pub fn parse_registry(params: &str) -> HashMap<Vec<u8>, StableSwapPoolConfig> {
+    let mut registry = HashMap::new();
+
+    for entry in params.split(',') {
+        let mut parts = entry.split(':');
+        let (Some(pool), Some(amp), Some(n_coins)) = (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+
+        let (Ok(amplification), Ok(n_coins)) = (amp.parse::<u64>(), n_coins.parse::<u32>()) else {
+            continue;
+        };
+
+        if let Some(pool_bytes) = parse_hex_address(pool) {
+            registry.insert(
+                pool_bytes,
+                StableSwapPoolConfig {
+                    amplification,
+                    n_coins,
+                },
+            );
+        }
+    }
+
+    registry
+}
+
+/// Store handler that reconstructs each registered pool's per-coin balances,
+/// reorg-safely, as a net flow accumulated from `TokenExchange` deltas: the
+/// sold coin's balance goes up by `tokens_sold`, the bought coin's goes down
+/// by `tokens_bought`. StableSwap pools have no `Sync`-style absolute-reserve
+/// event, so (like `pool_stats::store_pool_lifetime_reserves` for V3) this
+/// starts from zero rather than the pool's true balance at creation — a
+/// directional proxy, not an absolute figure. `process_stableswap_event`
+/// reads it back to price swaps off the pool's actual balances instead of
+/// the trade's own amounts.
+#[substreams::handlers::store]
+pub fn store_stableswap_reserves(params: String, block: eth::Block, store: StoreAddBigInt) {
+    let registry = parse_registry(&params);
+
+    for log in block.logs() {
+        if log.topics().is_empty() || log.topics()[0] != TOKEN_EXCHANGE_EVENT_SIG {
+            continue;
+        }
+        if log.data().len() < 128 {
+            continue;
+        }
+
+        let pool_address = log.log.address.to_vec();
+        let Some(config) = registry.get(&pool_address) else {
+            continue;
+        };
+
+        let sold_id = small_index_from_word(&log.data()[0..32]);
+        let tokens_sold = uint256_to_bigint(&log.data()[32..64]);
+        let bought_id = small_index_from_word(&log.data()[64..96]);
+        let tokens_bought = uint256_to_bigint(&log.data()[96..128]);
+
+        if sold_id >= config.n_coins as usize || bought_id >= config.n_coins as usize {
+            continue;
+        }
+
+        let pool_key = format_pool_address(&pool_address);
+        apply_coin_reserve_delta(
+            &store,
+            log.ordinal(),
+            &pool_key,
+            sold_id as u32,
+            tokens_sold,
+        );
+        apply_coin_reserve_delta(
+            &store,
+            log.ordinal(),
+            &pool_key,
+            bought_id as u32,
+            BigInt::zero() - tokens_bought,
+        );
+    }
+}
+
+/// Solve the StableSwap invariant `D` for balances `x_i` via Newton iteration.
+///
+/// `Ann*S + D = Ann*D + D^(n+1)/(n^n*P)`, iterated as:
+/// `D_P = D^(n+1) / (n^n * P)`, `D' = (Ann*S + n*D_P)*D / ((Ann-1)*D + (n+1)*D_P)`
+/// until `|D - D_prev| <= 1`, capped at 255 iterations.
+pub fn solve_d(balances: &[BigDecimal], amp: u64) -> BigDecimal {
+    let n = balances.len() as u64;
+    if n == 0 {
+        return BigDecimal::zero();
+    }
+
+    let s: BigDecimal = balances
+        .iter()
+        .cloned()
+        .fold(BigDecimal::zero(), |acc, balance| acc + balance);
+    if s == BigDecimal::zero() {
+        return BigDecimal::zero();
+    }
+
+    let ann = decimal(amp) * decimal(n.pow(n as u32));
+    let mut d = s.clone();
+
+    for _ in 0..255 {
+        let mut d_p = d.clone();
+        for balance in balances {
+            if *balance == BigDecimal::zero() {
+                continue;
+            }
+            d_p = d_p.clone() * d.clone() / (decimal(n) * balance.clone());
+        }
+
+        let d_prev = d.clone();
+        let numerator = (ann.clone() * s.clone() + decimal(n) * d_p.clone()) * d.clone();
+        let denominator = (ann.clone() - decimal(1)) * d.clone() + decimal(n + 1) * d_p;
+        d = numerator / denominator;
+
+        let diff = if d > d_prev {
+            d.clone() - d_prev
+        } else {
+            d_prev - d.clone()
+        };
+        if diff <= decimal(1) {
+            break;
+        }
+    }
+
+    d
+}
+
+/// Solve for the balance `y` of coin `j` that keeps the invariant `D` satisfied
+/// given every other coin's balance `x_i`.
+pub fn get_y(j: usize, balances: &[BigDecimal], d: &BigDecimal, amp: u64) -> BigDecimal {
+    let n = balances.len() as u64;
+    let ann = decimal(amp) * decimal(n.pow(n as u32));
+
+    let mut c = d.clone();
+    let mut s_ = BigDecimal::zero();
+
+    for (k, balance) in balances.iter().enumerate() {
+        if k == j {
+            continue;
+        }
+        s_ = s_ + balance.clone();
+        c = c.clone() * d.clone() / (decimal(n) * balance.clone());
+    }
+
+    c = c.clone() * d.clone() / (ann.clone() * decimal(n));
+    let b = s_ + d.clone() / ann;
+
+    let epsilon = BigDecimal::from_str(PRICE_EPSILON).unwrap();
+    let mut y = d.clone();
+    for _ in 0..255 {
+        let y_prev = y.clone();
+        y = (y.clone() * y.clone() + c.clone()) / (decimal(2) * y + b.clone() - d.clone());
+
+        let diff = if y > y_prev {
+            y.clone() - y_prev
+        } else {
+            y_prev - y.clone()
+        };
+        if diff <= epsilon {
+            break;
+        }
+    }
+
+    y
+}
+
+/// Marginal price of coin `j` in terms of coin `i`, derived as the finite
+/// difference `(get_y(x_i) - get_y(x_i + eps)) / eps`.
+pub fn spot_price(i: usize, j: usize, balances: &[BigDecimal], amp: u64) -> BigDecimal {
+    let d = solve_d(balances, amp);
+    let eps = BigDecimal::from_str(PRICE_EPSILON).unwrap();
+
+    let y0 = get_y(j, balances, &d, amp);
+
+    let mut bumped = balances.to_vec();
+    bumped[i] = bumped[i].clone() + eps.clone();
+    let y1 = get_y(j, &bumped, &d, amp);
+
+    (y0 - y1) / eps
+}
+
+/// Aggregated StableSwap ticker data for a single pool/block.
+#[derive(Clone)]
+pub struct StableSwapAggregation {
+    pub base: SwapAggregation,
+    pub close_price: BigDecimal,
+}
+
+impl Default for StableSwapAggregation {
+    fn default() -> Self {
+        Self {
+            base: SwapAggregation::default(),
+            close_price: BigDecimal::zero(),
+        }
+    }
+}
+
+/// Read a small non-negative index (coin id) out of a 32-byte big-endian word.
+fn small_index_from_word(word: &[u8]) -> usize {
+    *word.last().unwrap_or(&0) as usize
+}
+
+/// Process a Curve/Saddle-style `TokenExchange` event and update pool
+/// aggregations, deriving `close_price` from the StableSwap invariant instead
+/// of a `sqrtPriceX96` (which these pools never emit). The pool's `A` and coin
+/// count come from `registry`, since neither is carried on the event itself.
+/// The pool's actual per-coin balances come from `reserves_store` (see
+/// [`store_stableswap_reserves`]), which already reflects this trade's own
+/// delta.
+pub fn process_stableswap_event(
+    log: &LogView,
+    registry: &HashMap<Vec<u8>, StableSwapPoolConfig>,
+    reserves_store: &StoreGetBigInt,
+    pool_aggregations: &mut HashMap<Vec<u8>, StableSwapAggregation>,
+) {
+    // TokenExchange data layout: sold_id (int128), tokens_sold (uint256),
+    // bought_id (int128), tokens_bought (uint256) = 128 bytes.
+    if log.data().len() < 128 {
+        return;
+    }
+
+    let pool_address = log.log.address.to_vec();
+    let Some(config) = registry.get(&pool_address) else {
+        return;
+    };
+
+    let sold_id = small_index_from_word(&log.data()[0..32]);
+    let tokens_sold = uint256_to_bigint(&log.data()[32..64]);
+    let bought_id = small_index_from_word(&log.data()[64..96]);
+    let tokens_bought = uint256_to_bigint(&log.data()[96..128]);
+
+    if sold_id >= config.n_coins as usize || bought_id >= config.n_coins as usize {
+        return;
+    }
+
+    let entry = pool_aggregations.entry(pool_address).or_default();
+
+    // Map the indexed coins onto token0/token1 volume the same way V2/V3 do.
+    if sold_id == 0 || bought_id == 0 {
+        let amount0 = if sold_id == 0 {
+            &tokens_sold
+        } else {
+            &tokens_bought
+        };
+        entry.base.volume_token0 = entry.base.volume_token0.clone() + amount0.clone();
+    }
+    if sold_id == 1 || bought_id == 1 {
+        let amount1 = if sold_id == 1 {
+            &tokens_sold
+        } else {
+            &tokens_bought
+        };
+        entry.base.volume_token1 = entry.base.volume_token1.clone() + amount1.clone();
+    }
+    entry.base.swap_count += 1;
+    entry.base.last_traded_indices = Some((sold_id as u32, bought_id as u32));
+
+    // The pool's actual live per-coin balances, not the trade's own amounts
+    // (which would be economically meaningless — a $10 swap and a $10M swap
+    // in the same pool would report wildly different "prices" — and flat
+    // wrong here for any n_coins > 2 pool, since only 2 of n coins' amounts
+    // are ever on the event).
+    let pool_key = format_pool_address(&pool_address);
+    let balances: Vec<BigDecimal> = (0..config.n_coins)
+        .map(|i| {
+            BigDecimal::from(
+                reserves_store
+                    .get_last(format!("{pool_key}:coin{i}"))
+                    .unwrap_or_default(),
+            )
+        })
+        .collect();
+    entry.close_price = spot_price(sold_id, bought_id, &balances, config.amplification);
+}
+
+pub fn format_close_price(aggregation: &StableSwapAggregation) -> String {
+    format_bigdecimal(&aggregation.close_price)
+}
+
+/// [`DexAdapter`] for Curve/Saddle-style `TokenExchange` events. Unlike
+/// [`process_stableswap_event`], this doesn't need the pool's amplification
+/// or coin-count registry: `NormalizedSwap` only carries volume, not a
+/// derived price, so mapping coin ids 0/1 onto token0/1 is all it does.
+pub struct StableSwapAdapter;
+
+impl DexAdapter for StableSwapAdapter {
+    fn swap_event_signature(&self) -> [u8; 32] {
+        TOKEN_EXCHANGE_EVENT_SIG
+    }
+
+    fn decode_swap(&self, log: &LogView) -> Option<NormalizedSwap> {
+        if log.data().len() < 128 {
+            return None;
+        }
+
+        let sold_id = small_index_from_word(&log.data()[0..32]);
+        let tokens_sold = uint256_to_bigint(&log.data()[32..64]);
+        let bought_id = small_index_from_word(&log.data()[64..96]);
+        let tokens_bought = uint256_to_bigint(&log.data()[96..128]);
+
+        let amount0 = match (sold_id, bought_id) {
+            (0, _) => tokens_sold.clone(),
+            (_, 0) => tokens_bought.clone(),
+            _ => BigInt::zero(),
+        };
+        let amount1 = match (sold_id, bought_id) {
+            (1, _) => tokens_sold,
+            (_, 1) => tokens_bought,
+            _ => BigInt::zero(),
+        };
+
+        Some(NormalizedSwap {
+            pool_address: log.log.address.to_vec(),
+            amount0,
+            amount1,
+            sqrt_price: None,
+            reserves: None,
+            traded_indices: Some((sold_id as u32, bought_id as u32)),
+        })
+    }
+}
+
+pub fn format_pool_address(pool_address_bytes: &[u8]) -> String {
+    ensure_0x_prefix(&substreams::Hex(pool_address_bytes).to_string())
+}
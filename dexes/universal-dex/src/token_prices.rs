@@ -0,0 +1,265 @@
+// ─────────────────────────────────────────────────────────────────────────────
+// On-chain reference-price resolution (derivedEth per token, ETH/USD rate)
+// ─────────────────────────────────────────────────────────────────────────────
+
+use crate::stableswap::parse_hex_address;
+use dex_common::ensure_0x_prefix;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use substreams::scalar::{BigDecimal, BigInt};
+use substreams::store::{StoreGet, StoreGetBigInt, StoreNew, StoreSet, StoreSetBigDecimal};
+use substreams_ethereum::pb::eth::v2 as eth;
+
+/// Key `store_token_prices` writes the WETH↔stablecoin reference rate under.
+/// Not a valid `0x`-prefixed address, so it can't collide with a token key.
+pub const ETH_USD_KEY: &str = "ETH_USD";
+
+/// Per-pool token metadata needed to resolve a reference price: the two
+/// token addresses and their decimals. Neither is carried on a `Sync` event,
+/// so (like [`crate::stableswap::parse_registry`]) it's supplied out of band
+/// via `params`.
+#[derive(Clone)]
+pub struct PoolTokenConfig {
+    pub token0: Vec<u8>,
+    pub token1: Vec<u8>,
+    pub decimals0: u32,
+    pub decimals1: u32,
+}
+
+/// Parse `params` into the WETH address, the whitelisted stablecoin base
+/// assets, and the pool token registry. Format: comma-separated entries of
+/// either `weth=<address>`, `base=<address>` (repeatable, one per
+/// stablecoin), or `pool:token0:token1:decimals0:decimals1`.
+pub fn parse_params(
+    params: &str,
+) -> (
+    Option<Vec<u8>>,
+    HashSet<Vec<u8>>,
+    HashMap<Vec<u8>, PoolTokenConfig>,
+) {
+    let mut weth = None;
+    let mut bases = HashSet::new();
+    let mut registry = HashMap::new();
+
+    for entry in params.split(',') {
+        let mut kv = entry.splitn(2, '=');
+        match (kv.next(), kv.next()) {
+            (Some("weth"), Some(address)) => {
+                if let Some(bytes) = parse_hex_address(address) {
+                    bases.insert(bytes.clone());
+                    weth = Some(bytes);
+                }
+            }
+            (Some("base"), Some(address)) => {
+                if let Some(bytes) = parse_hex_address(address) {
+                    bases.insert(bytes);
+                }
+            }
+            _ => {
+                let mut parts = entry.split(':');
+                let (Some(pool), Some(token0), Some(token1), Some(decimals0), Some(decimals1)) = (
+                    parts.next(),
+                    parts.next(),
+                    parts.next(),
+                    parts.next(),
+                    parts.next(),
+                ) else {
+                    continue;
+                };
+
+                let (Ok(decimals0), Ok(decimals1)) =
+                    (decimals0.parse::<u32>(), decimals1.parse::<u32>())
+                else {
+                    continue;
+                };
+
+                if let (Some(pool), Some(token0), Some(token1)) = (
+                    parse_hex_address(pool),
+                    parse_hex_address(token0),
+                    parse_hex_address(token1),
+                ) {
+                    registry.insert(
+                        pool,
+                        PoolTokenConfig {
+                            token0,
+                            token1,
+                            decimals0,
+                            decimals1,
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    (weth, bases, registry)
+}
+
+/// Decimal-adjusted price of `token0` in terms of `token1`, from raw reserves.
+fn price_token0_in_token1(
+    reserve0: &BigInt,
+    reserve1: &BigInt,
+    decimals0: u32,
+    decimals1: u32,
+) -> Option<BigDecimal> {
+    if *reserve0 == BigInt::zero() {
+        return None;
+    }
+    let scale0 = BigDecimal::from_str(&format!("1e{decimals0}")).ok()?;
+    let scale1 = BigDecimal::from_str(&format!("1e{decimals1}")).ok()?;
+    Some(
+        (BigDecimal::from(reserve1.clone()) / scale1)
+            / (BigDecimal::from(reserve0.clone()) / scale0),
+    )
+}
+
+/// A resolved reference price for one non-base token: how much of `base_token`
+/// one unit of it trades for, plus the raw `token1` reserve of the pool it was
+/// resolved from (the tie-break "largest token1 reserve" uses).
+struct Candidate {
+    is_weth_priced: bool,
+    price_in_base: BigDecimal,
+    reserve1: BigInt,
+}
+
+/// Store handler that maintains a `derivedEth` reference price per token
+/// address (the Uniswap-subgraph convention: a token's price expressed in
+/// ETH), plus the ETH/USD rate under [`ETH_USD_KEY`], both resolved purely
+/// from on-chain reserves — no external price oracle.
+///
+/// Only pools registered via `params` (see [`parse_params`]) are considered,
+/// and only the V2 `Sync`-derived reserves `store_reserves` already tracks:
+/// V3 pools don't persist a reserve pair here, so they can't currently
+/// contribute a reference price (consistent with `reserve0`/`reserve1` on
+/// [`crate::map_dex_ticker_output`]'s `PoolTicker`, which are V2-only too).
+/// For a token paired directly with WETH, `derivedEth` is the pool's own
+/// ratio; for a token paired with a stablecoin, it's that ratio divided by
+/// the ETH/USD rate. Among pools pairing the same token with a base asset,
+/// the one with the largest raw `token1` reserve wins, to resist thin-pool
+/// price manipulation.
+#[substreams::handlers::store]
+pub fn store_token_prices(
+    params: String,
+    _block: eth::Block,
+    reserves_store: StoreGetBigInt,
+    store: StoreSetBigDecimal,
+) {
+    let (weth, bases, registry) = parse_params(&params);
+    let Some(weth) = weth else { return };
+
+    let mut best: HashMap<Vec<u8>, Candidate> = HashMap::new();
+    let mut best_eth_usd: Option<(BigDecimal, BigInt)> = None;
+
+    for (pool, config) in &registry {
+        let pool_address = ensure_0x_prefix(&substreams::Hex(pool).to_string());
+        let Some(reserve0) = reserves_store.get_last(format!("{pool_address}:r0")) else {
+            continue;
+        };
+        let Some(reserve1) = reserves_store.get_last(format!("{pool_address}:r1")) else {
+            continue;
+        };
+
+        // In a WETH/stablecoin pool WETH is the side being priced (that pool
+        // is the ETH/USD reference), so only the stablecoin counts as base.
+        let token0_is_base = bases.contains(&config.token0)
+            && !(config.token0 == weth && bases.contains(&config.token1));
+        let token1_is_base = bases.contains(&config.token1)
+            && !(config.token1 == weth && bases.contains(&config.token0));
+        if token0_is_base == token1_is_base {
+            // Neither side is a whitelisted base asset (nothing to price
+            // against), or both are stablecoins (no new information) — skip.
+            continue;
+        }
+
+        let (non_base_token, price_in_base, base_token) = if token1_is_base {
+            let Some(price) =
+                price_token0_in_token1(&reserve0, &reserve1, config.decimals0, config.decimals1)
+            else {
+                continue;
+            };
+            (&config.token0, price, &config.token1)
+        } else {
+            let Some(price) =
+                price_token0_in_token1(&reserve1, &reserve0, config.decimals1, config.decimals0)
+            else {
+                continue;
+            };
+            (&config.token1, price, &config.token0)
+        };
+
+        let is_weth_priced = base_token == &weth;
+        let candidate = Candidate {
+            is_weth_priced,
+            price_in_base: price_in_base.clone(),
+            reserve1: reserve1.clone(),
+        };
+        let better = match best.get(non_base_token) {
+            // A direct WETH pairing always outranks a stablecoin pairing
+            // (one fewer hop), regardless of reserve size.
+            Some(existing) if is_weth_priced && !existing.is_weth_priced => true,
+            Some(existing) if !is_weth_priced && existing.is_weth_priced => false,
+            Some(existing) => reserve1 > existing.reserve1,
+            None => true,
+        };
+        if better {
+            best.insert(non_base_token.clone(), candidate);
+        }
+
+        // The WETH/stablecoin reference pool also gives the ETH/USD rate.
+        if non_base_token == &weth {
+            let better = best_eth_usd
+                .as_ref()
+                .map(|(_, r)| reserve1 > *r)
+                .unwrap_or(true);
+            if better {
+                best_eth_usd = Some((price_in_base, reserve1.clone()));
+            }
+        }
+    }
+
+    // WETH-priced tokens need no ETH/USD rate at all, so write those (and
+    // WETH itself, 1 ETH by definition) regardless of whether a
+    // WETH/stablecoin reference pool was found this block.
+    for (token, candidate) in &best {
+        if candidate.is_weth_priced {
+            let token_address = ensure_0x_prefix(&substreams::Hex(token).to_string());
+            store.set(0, token_address, &candidate.price_in_base);
+        }
+    }
+    store.set(
+        0,
+        ensure_0x_prefix(&substreams::Hex(&weth).to_string()),
+        &BigDecimal::from_str("1").unwrap(),
+    );
+
+    // Everything below needs the ETH/USD rate: stablecoin-priced tokens
+    // (convert their base-asset price into ETH terms), and the stablecoin
+    // base assets themselves. Skip it all if no reference pool traded.
+    let Some((eth_usd, _)) = best_eth_usd else {
+        return;
+    };
+    store.set(0, ETH_USD_KEY, &eth_usd);
+
+    if eth_usd == BigDecimal::zero() {
+        return;
+    }
+
+    for (token, candidate) in &best {
+        if !candidate.is_weth_priced {
+            let derived_eth = candidate.price_in_base.clone() / eth_usd.clone();
+            let token_address = ensure_0x_prefix(&substreams::Hex(token).to_string());
+            store.set(0, token_address, &derived_eth);
+        }
+    }
+
+    let stablecoin_derived_eth = BigDecimal::from_str("1").unwrap() / eth_usd;
+    for base in &bases {
+        if base != &weth {
+            store.set(
+                0,
+                ensure_0x_prefix(&substreams::Hex(base).to_string()),
+                &stablecoin_derived_eth,
+            );
+        }
+    }
+}
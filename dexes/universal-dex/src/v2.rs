@@ -1,6 +1,10 @@
+use crate::adapter::{DexAdapter, NormalizedSwap};
 use crate::common::SwapAggregation;
-use dex_common::{calculate_sqrt_price_x96, uint112_to_bigint, uint256_to_bigint};
+use dex_common::{
+    calculate_sqrt_price_x96, price_from_sqrt_price_x96, uint112_to_bigint, uint256_to_bigint,
+};
 use std::collections::HashMap;
+use substreams::scalar::{BigDecimal, BigInt};
 use substreams_ethereum::block_view::LogView;
 
 /// Process a V2 Swap event and update pool aggregations
@@ -39,9 +43,20 @@ pub fn process_swap_event(
 
     // Calculate volumes
     // For V2, volume is the sum of in and out amounts (one will be 0 for each direction)
-    entry.volume_token0 = entry.volume_token0.clone() + amount0_in + amount0_out;
-    entry.volume_token1 = entry.volume_token1.clone() + amount1_in + amount1_out;
+    entry.volume_token0 = entry.volume_token0.clone() + amount0_in.clone() + amount0_out.clone();
+    entry.volume_token1 = entry.volume_token1.clone() + amount1_in.clone() + amount1_out.clone();
     entry.swap_count += 1;
+
+    // Direction follows from which of amount0In/amount1In is non-zero.
+    entry.observe_swap_direction(&amount0_in, &amount1_in, &amount0_out, &amount1_out);
+
+    // Weight VWAP using the price from the most recent Sync (Sync is always
+    // emitted before Swap within a transaction, so this reflects the
+    // post-swap reserves for this exact trade). Skip if no Sync seen yet.
+    if entry.last_sqrt_price > BigInt::zero() {
+        let price = price_from_sqrt_price_x96(&entry.last_sqrt_price);
+        entry.observe_vwap_sample(&price, &BigDecimal::from(amount1_in + amount1_out));
+    }
 }
 
 /// Process a V2 Sync event and update pool aggregations
@@ -71,4 +86,42 @@ pub fn process_sync_event(
     // Calculate sqrtPriceX96 from reserves to match V3 output format
     // sqrtPriceX96 = sqrt(reserve1 / reserve0) * 2^96
     entry.last_sqrt_price = calculate_sqrt_price_x96(&reserve0, &reserve1);
+
+    // Track intra-block high/low off the reserve-derived price
+    let price = price_from_sqrt_price_x96(&entry.last_sqrt_price);
+    entry.observe_price(&price);
+}
+
+/// [`DexAdapter`] wrapping the Uniswap V2 `Swap` event decode above, for use
+/// through the generic [`crate::adapter::AdapterRegistry`]. It only covers
+/// the `Swap` log itself; V2's reserve-derived price still requires the
+/// paired `Sync` log, which this trait has no concept of, so
+/// `process_sync_event` remains the source of truth for price in the main
+/// ticker pipeline.
+pub struct UniswapV2Adapter;
+
+impl DexAdapter for UniswapV2Adapter {
+    fn swap_event_signature(&self) -> [u8; 32] {
+        crate::V2_SWAP_EVENT_SIG
+    }
+
+    fn decode_swap(&self, log: &LogView) -> Option<NormalizedSwap> {
+        if log.data().len() < 128 || log.topics().len() < 3 {
+            return None;
+        }
+
+        let amount0_in = uint256_to_bigint(&log.data()[0..32]);
+        let amount1_in = uint256_to_bigint(&log.data()[32..64]);
+        let amount0_out = uint256_to_bigint(&log.data()[64..96]);
+        let amount1_out = uint256_to_bigint(&log.data()[96..128]);
+
+        Some(NormalizedSwap {
+            pool_address: log.log.address.to_vec(),
+            amount0: amount0_in + amount0_out,
+            amount1: amount1_in + amount1_out,
+            sqrt_price: None,
+            reserves: None,
+            traded_indices: None,
+        })
+    }
 }
@@ -1,11 +1,15 @@
+use crate::adapter::{DexAdapter, NormalizedSwap};
 use crate::common::SwapAggregation;
-use dex_common::{int256_to_bigint, uint160_to_bigint};
+use dex_common::{
+    int24_to_i32, int256_to_bigint, price_from_sqrt_price_x96, uint128_to_bigint, uint160_to_bigint,
+};
 use std::collections::HashMap;
-use substreams::scalar::BigInt;
+use substreams::scalar::{BigDecimal, BigInt};
 use substreams_ethereum::block_view::LogView;
 
 /// Process a V3 Swap event and update pool aggregations
-/// Works for both Uniswap V3 and PancakeSwap V3 (ignores protocol fees)
+/// Works for both Uniswap V3 and PancakeSwap V3 (accumulates PancakeSwap's
+/// extra protocolFeesToken0/1 words when present)
 pub fn process_swap_event(
     log: &LogView,
     pool_aggregations: &mut HashMap<Vec<u8>, SwapAggregation>,
@@ -16,7 +20,8 @@ pub fn process_swap_event(
     // - topics[2]: indexed recipient address
     // - data: amount0 (int256), amount1 (int256), sqrtPriceX96 (uint160), liquidity (uint128), tick (int24)
     // Data layout: 32 + 32 + 32 + 32 + 32 = 160 bytes minimum
-    // PancakeSwap V3 has 64 extra bytes (protocolFeesToken0, protocolFeesToken1) which we ignore
+    // PancakeSwap V3 appends 64 extra bytes (protocolFeesToken0, protocolFeesToken1),
+    // accumulated below when present
     if log.data().len() < 160 || log.topics().len() < 3 {
         return;
     }
@@ -35,6 +40,9 @@ pub fn process_swap_event(
     // Calculate absolute volumes
     // Swap amounts are signed: negative = tokens out, positive = tokens in
     // We need absolute values since volume tracks total traded regardless of direction
+    let amount0_is_in = amount0 > BigInt::zero();
+    let amount1_is_in = amount1 > BigInt::zero();
+
     let abs_amount0 = if amount0 < BigInt::zero() {
         amount0.neg()
     } else {
@@ -47,12 +55,106 @@ pub fn process_swap_event(
         amount1
     };
 
-    entry.volume_token0 = entry.volume_token0.clone() + abs_amount0;
-    entry.volume_token1 = entry.volume_token1.clone() + abs_amount1;
+    entry.volume_token0 = entry.volume_token0.clone() + abs_amount0.clone();
+    entry.volume_token1 = entry.volume_token1.clone() + abs_amount1.clone();
     entry.swap_count += 1;
 
+    // V3's signed amounts already encode direction: positive = into the
+    // pool, negative = out of it.
+    let zero = BigInt::zero();
+    if amount0_is_in {
+        entry.observe_swap_direction(&abs_amount0, &zero, &zero, &abs_amount1);
+    } else if amount1_is_in {
+        entry.observe_swap_direction(&zero, &abs_amount1, &abs_amount0, &zero);
+    }
+
     // Parse sqrtPriceX96 (uint160) - bytes 64-96
     // This is the raw sqrtPriceX96 value that clients will use to calculate price
     let price_bytes = &log.data()[64..96];
     entry.last_sqrt_price = uint160_to_bigint(price_bytes);
+
+    // Track intra-block high/low and volume-weighted average price
+    let price = price_from_sqrt_price_x96(&entry.last_sqrt_price);
+    entry.observe_price(&price);
+    entry.observe_vwap_sample(&price, &BigDecimal::from(abs_amount1));
+
+    // Parse liquidity (uint128) - bytes 96-128
+    // In-range active liquidity at the time of the swap, used to convert
+    // sqrtPriceX96 into a usable depth chart downstream.
+    entry.last_liquidity = uint128_to_bigint(&log.data()[96..128]);
+
+    // Parse tick (int24, sign-extended) - bytes 128-160
+    entry.current_tick = int24_to_i32(&log.data()[128..160]);
+
+    // PancakeSwap V3 appends protocolFeesToken0/1 (uint128 each) after the
+    // standard 160-byte layout; standard Uniswap V3 events stop at 160 bytes,
+    // so this is a no-op for them.
+    if log.data().len() >= 224 {
+        entry.protocol_fees_token0 =
+            entry.protocol_fees_token0.clone() + uint128_to_bigint(&log.data()[160..192]);
+        entry.protocol_fees_token1 =
+            entry.protocol_fees_token1.clone() + uint128_to_bigint(&log.data()[192..224]);
+    }
+}
+
+/// Decode the 160-byte V3 `Swap` layout shared by Uniswap V3 and PancakeSwap
+/// V3, used by both adapters below (they differ only in event signature).
+fn decode_v3_swap(log: &LogView) -> Option<NormalizedSwap> {
+    if log.data().len() < 160 || log.topics().len() < 3 {
+        return None;
+    }
+
+    let amount0 = int256_to_bigint(&log.data()[0..32]);
+    let amount1 = int256_to_bigint(&log.data()[32..64]);
+    let sqrt_price = uint160_to_bigint(&log.data()[64..96]);
+
+    let abs_amount0 = if amount0 < BigInt::zero() {
+        amount0.neg()
+    } else {
+        amount0
+    };
+    let abs_amount1 = if amount1 < BigInt::zero() {
+        amount1.neg()
+    } else {
+        amount1
+    };
+
+    Some(NormalizedSwap {
+        pool_address: log.log.address.to_vec(),
+        amount0: abs_amount0,
+        amount1: abs_amount1,
+        sqrt_price: Some(sqrt_price),
+        reserves: None,
+        traded_indices: None,
+    })
+}
+
+/// [`DexAdapter`] wrapping the Uniswap V3 `Swap` event decode above, for use
+/// through the generic [`crate::adapter::AdapterRegistry`].
+pub struct UniswapV3Adapter;
+
+impl DexAdapter for UniswapV3Adapter {
+    fn swap_event_signature(&self) -> [u8; 32] {
+        crate::UNISWAP_V3_SWAP_EVENT_SIG
+    }
+
+    fn decode_swap(&self, log: &LogView) -> Option<NormalizedSwap> {
+        decode_v3_swap(log)
+    }
+}
+
+/// Same decode as [`UniswapV3Adapter`], registered under PancakeSwap V3's
+/// distinct `Swap` signature (PancakeSwap's extra protocol-fee words aren't
+/// part of `NormalizedSwap`, so they're dropped here; `process_swap_event`
+/// above still accumulates them for the main ticker pipeline).
+pub struct PancakeswapV3Adapter;
+
+impl DexAdapter for PancakeswapV3Adapter {
+    fn swap_event_signature(&self) -> [u8; 32] {
+        crate::PANCAKESWAP_V3_SWAP_EVENT_SIG
+    }
+
+    fn decode_swap(&self, log: &LogView) -> Option<NormalizedSwap> {
+        decode_v3_swap(log)
+    }
 }